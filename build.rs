@@ -20,7 +20,9 @@ mod test {
         let test_fonts = read_dir("./fonts/plain/").unwrap();
 
         for font in test_fonts {
-            write_test("plain_", &mut test_file, &font.unwrap());
+            let font = font.unwrap();
+            write_test("plain_", &mut test_file, &font);
+            write_roundtrip_test("plain_", &mut test_file, &font);
         }
 
         if cfg!(feature = "zip") {
@@ -43,13 +45,24 @@ use figfont::FIGfont;
         .unwrap();
     }
 
-    fn write_test(prefix: &str, test_file: &mut File, entry: &DirEntry) {
-        if entry.file_type().unwrap().is_file() && entry.path().extension().unwrap() == "flf" {
+    fn font_test(entry: &DirEntry) -> Option<(String, String)> {
+        if !entry.file_type().unwrap().is_file() {
+            return None;
+        }
+        if matches!(entry.path().extension(), Some(ext) if ext == "flf" || ext == "tlf") {
             let p = canonicalize(entry.path()).unwrap();
-            let path = p.to_str().unwrap();
+            let path = p.to_str().unwrap().to_owned();
             let test_name = p.file_name().unwrap().to_str().unwrap();
-            let test_name = &test_name[..(test_name.len() - 4)];
+            let test_name = test_name[..(test_name.len() - 4)].to_owned();
+
+            Some((test_name, path))
+        } else {
+            None
+        }
+    }
 
+    fn write_test(prefix: &str, test_file: &mut File, entry: &DirEntry) {
+        if let Some((test_name, path)) = font_test(entry) {
             write!(
                 test_file,
                 include_str!("./tests/test_template"),
@@ -59,4 +72,16 @@ use figfont::FIGfont;
             .unwrap();
         }
     }
+
+    fn write_roundtrip_test(prefix: &str, test_file: &mut File, entry: &DirEntry) {
+        if let Some((test_name, path)) = font_test(entry) {
+            write!(
+                test_file,
+                include_str!("./tests/test_roundtrip_template"),
+                name = format!("{}{}", prefix, test_name),
+                path = path
+            )
+            .unwrap();
+        }
+    }
 }
@@ -0,0 +1,221 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::{
+    character::{self, FIGcharacter},
+    codetag::Codetag,
+    error::Error,
+    header::Header,
+    result::Result,
+    FIGfont,
+};
+
+/// Builder for creating a FIGfont or editing an existing one.
+///
+/// ```
+/// use figfont::{FIGcharacter, FIGfontBuilder, HeaderBuilder};
+///
+/// let header = HeaderBuilder::new(1).build().unwrap();
+/// let font = FIGfontBuilder::new(header)
+///     .with_character('A', FIGcharacter::from_rows(["/\\"], '$'))
+///     .with_character('B', FIGcharacter::from_rows(["|3"], '$'))
+///     .build()
+///     .unwrap();
+/// assert_eq!(font.render("AB").unwrap().to_string(), "/\\|3");
+/// ```
+#[derive(Debug, Clone)]
+pub struct FIGfontBuilder {
+    header: Header,
+    characters: HashMap<i32, FIGcharacter>,
+    codetagged: HashSet<i32>,
+}
+
+impl FIGfontBuilder {
+    /// Create a builder for a font without characters.
+    pub fn new(header: Header) -> Self {
+        FIGfontBuilder {
+            header,
+            characters: HashMap::new(),
+            codetagged: HashSet::new(),
+        }
+    }
+
+    /// Get the header.
+    pub fn header(&self) -> &Header {
+        &self.header
+    }
+
+    /// Replace the header.
+    pub fn set_header(&mut self, header: Header) {
+        self.header = header;
+    }
+
+    /// Get the FIGcharacter for `code`, if any.
+    pub fn get<C: Into<Codetag>>(&self, code: C) -> Option<&FIGcharacter> {
+        self.characters.get(&code.into().code())
+    }
+
+    /// Insert the FIGcharacter for `code`, returning the one it replaces.
+    ///
+    /// Characters outside of the required ones (ASCII and Deutsch), and the
+    /// ones with a comment, are written as codetagged characters.
+    pub fn insert<C: Into<Codetag>>(
+        &mut self,
+        code: C,
+        character: FIGcharacter,
+    ) -> Option<FIGcharacter> {
+        let code = code.into().code();
+        if character.comment().is_some() {
+            self.codetagged.insert(code);
+        }
+        self.characters.insert(code, character)
+    }
+
+    /// Remove the FIGcharacter for `code`, returning it.
+    pub fn remove<C: Into<Codetag>>(&mut self, code: C) -> Option<FIGcharacter> {
+        let code = code.into().code();
+        self.codetagged.remove(&code);
+        self.characters.remove(&code)
+    }
+
+    /// Insert the FIGcharacter for `code`, replacing the existing one.
+    pub fn with_character<C: Into<Codetag>>(mut self, code: C, character: FIGcharacter) -> Self {
+        self.insert(code, character);
+        self
+    }
+
+    /// Validate and build the FIGfont: every FIGcharacter must be as tall as
+    /// the font. The header's max length is raised to the longest line the
+    /// FIGcharacters are written with.
+    pub fn build(mut self) -> Result<FIGfont> {
+        let expected = self.header.height();
+        if let Some((code, character)) = self
+            .characters
+            .iter()
+            .filter(|(_, character)| character.height() != expected)
+            .min_by_key(|(code, _)| **code)
+        {
+            return Err(Error::InvalidCharacterHeight {
+                codetag: Codetag::from(*code),
+                height: character.height(),
+                expected,
+            });
+        }
+
+        // Characters that can't be encoded fail when writing instead.
+        let max_length = self
+            .characters
+            .values()
+            .filter_map(|character| character.encode(&self.header).ok())
+            .flatten()
+            .map(|row| character::line_length(&row, self.header.encoding()))
+            .fold(self.header.max_length(), usize::max);
+        self.header.set_max_length(max_length);
+
+        Ok(FIGfont::new(self.header, self.characters, self.codetagged))
+    }
+}
+
+impl From<FIGfont> for FIGfontBuilder {
+    #[inline]
+    fn from(font: FIGfont) -> Self {
+        let (header, characters, codetagged) = font.into_parts();
+        FIGfontBuilder {
+            header,
+            characters,
+            codetagged,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::FIGfontBuilder;
+    use crate::{
+        error::Error, Codetag, FIGcharacter, FIGfont, HeaderBuilder, Report, SubCharacter,
+    };
+
+    #[test]
+    fn build() {
+        let header = HeaderBuilder::new(2).build().unwrap();
+        let mut builder = FIGfontBuilder::new(header)
+            .with_character(' ', FIGcharacter::from_rows(["$", "$"], '$'))
+            .with_character('o', FIGcharacter::from_rows(["  ", "()"], '$'))
+            .with_character(
+                0x2022,
+                FIGcharacter::from_rows(["", "*"], '$').with_comment("BULLET"),
+            );
+
+        let bullet = builder.get('\u{2022}').unwrap();
+        assert_eq!(bullet.width(), 1);
+        assert_eq!(bullet.lines()[0][0], SubCharacter::Symbol(" ".into()));
+        assert_eq!(builder.get(32).unwrap().lines()[0][0], SubCharacter::Blank);
+
+        let old = builder.insert('o', FIGcharacter::from_rows(["  ", "<>"], '$'));
+        assert_eq!(old.unwrap().lines()[1][0], SubCharacter::Symbol("(".into()));
+        assert!(builder.remove('x').is_none());
+
+        let font = builder.clone().build().unwrap();
+        assert_eq!(
+            font.render("o \u{2022}").unwrap().to_lines(),
+            ["    ", "<> *"]
+        );
+
+        let mut written = Vec::new();
+        font.write_to(&mut written).unwrap();
+        let read = FIGfont::read_from(&written[..]).unwrap();
+        assert_eq!(read.try_get(0x2022).unwrap().comment(), Some("BULLET"));
+        assert_eq!(read.try_get('o' as i32), font.try_get('o' as i32));
+
+        builder.remove('o');
+        let font = builder.build().unwrap();
+        assert!(!font.contains('o'));
+
+        let mut builder = FIGfontBuilder::from(font);
+        builder.insert('x', FIGcharacter::from_rows(["x"], '$'));
+        assert!(matches!(
+            builder.build(),
+            Err(Error::InvalidCharacterHeight {
+                codetag: Codetag::Char('x'),
+                height: 1,
+                expected: 2,
+            })
+        ));
+    }
+
+    #[test]
+    fn valid_output() {
+        let header = HeaderBuilder::new(1).build().unwrap();
+        let font = FIGfontBuilder::new(header)
+            .with_character('A', FIGcharacter::from_rows(["/A\\"], '$'))
+            .build()
+            .unwrap();
+        assert_eq!(font.header().max_length(), 5);
+        let mut written = Vec::new();
+        font.write_to(&mut written).unwrap();
+        let report = Report::read_from(&written[..]).unwrap();
+        assert_eq!(report.problems(), []);
+
+        let mut builder = FIGfontBuilder::from(FIGfont::standard().unwrap());
+        builder.insert('-', FIGcharacter::from_rows(vec!["=".repeat(40); 6], '$'));
+        let mut written = Vec::new();
+        builder.build().unwrap().write_to(&mut written).unwrap();
+        let report = Report::read_from(&written[..]).unwrap();
+        assert_eq!(report.problems(), []);
+        let font = FIGfont::read_from(&written[..]).unwrap();
+        assert_eq!(font.header().max_length(), 42);
+    }
+
+    #[test]
+    fn hard_blank_symbol() {
+        let header = HeaderBuilder::new(1).build().unwrap();
+        let font = FIGfontBuilder::new(header)
+            .with_character('A', FIGcharacter::from_rows(["$A"], '#'))
+            .build()
+            .unwrap();
+
+        assert!(matches!(
+            font.write_to(Vec::new()),
+            Err(Error::HardBlankSymbol)
+        ));
+    }
+}
@@ -1,38 +1,92 @@
 use std::{
-    io::{BufReader, Read},
+    convert::TryFrom,
+    io::{Read, Write},
+    ops::Range,
     str::from_utf8,
+    sync::OnceLock,
 };
 
-use encoding::{all::ISO_8859_1, DecoderTrap, Encoding};
-
 use crate::{
-    error::{Error, ParseError},
+    charset::Encoding,
+    codetag::Codetag,
+    error::{Error, ParseError, ParseErrorKind},
     header::Header,
     result::Result,
     subcharacter::SubCharacter,
-    utils::{read_last_line, read_line},
+    utils::LineReader,
 };
 
 /// The FIGcharacter is the representation of a single large FIGfont character.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct FIGcharacter {
     comment: Option<Box<str>>,
     lines: Box<[Box<[SubCharacter]>]>,
 }
 
 impl FIGcharacter {
-    pub(crate) fn parse<R: Read>(
-        bread: &mut BufReader<R>,
-        header: &Header,
-    ) -> Result<FIGcharacter> {
-        read_character(bread, header)
+    /// Create a FIGcharacter from rows of SubCharacters. Shorter rows are
+    /// padded with spaces.
+    pub fn new<I>(lines: I) -> FIGcharacter
+    where
+        I: IntoIterator<Item = Vec<SubCharacter>>,
+    {
+        FIGcharacter {
+            comment: None,
+            lines: pad_lines(lines.into_iter().collect()),
+        }
     }
 
-    pub(crate) fn parse_with_codetag<R: Read>(
-        bread: &mut BufReader<R>,
+    /// Create a FIGcharacter from rows of text, in which `hard_blank` stands
+    /// for a hard blank. Shorter rows are padded with spaces.
+    pub fn from_rows<I, S>(rows: I, hard_blank: char) -> FIGcharacter
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let mut blank = [0; 4];
+        let blank = hard_blank.encode_utf8(&mut blank).as_bytes();
+
+        Self::new(rows.into_iter().map(|row| {
+            SubCharacter::split_utf8(row.as_ref().as_bytes(), blank).unwrap_or_default()
+        }))
+    }
+
+    /// Set the comment of the FIGcharacter. It is only kept for codetagged
+    /// characters.
+    pub fn with_comment<S: Into<String>>(mut self, comment: S) -> FIGcharacter {
+        self.comment = Some(comment.into().into_boxed_str());
+        self
+    }
+
+    /// Encode the rows as they are written, endmarks included.
+    pub(crate) fn encode(&self, header: &Header) -> Result<Vec<Vec<u8>>> {
+        encode_character(self, header)
+    }
+
+    pub(crate) fn write_codetag<W: Write>(
+        &self,
+        writer: &mut W,
         header: &Header,
-    ) -> Result<(i32, FIGcharacter)> {
-        read_character_with_codetag(bread, header)
+        code: i32,
+    ) -> Result<()> {
+        write!(writer, "{}", Codetag::from(code))?;
+        if let Some(comment) = self.comment() {
+            writer.write_all(b" ")?;
+            writer.write_all(&header.encoding().encode(comment)?)?;
+        }
+        writeln!(writer)?;
+
+        Ok(())
+    }
+
+    /// Get a FIGcharacter without lines.
+    pub(crate) fn empty() -> &'static FIGcharacter {
+        static EMPTY: OnceLock<FIGcharacter> = OnceLock::new();
+
+        EMPTY.get_or_init(|| FIGcharacter {
+            comment: None,
+            lines: Box::new([]),
+        })
     }
 
     /// Get the matrix of SubCharacters.
@@ -57,107 +111,276 @@ impl FIGcharacter {
     }
 }
 
-fn read_character_with_codetag<R: Read>(
-    bread: &mut BufReader<R>,
-    header: &Header,
-) -> Result<(i32, FIGcharacter)> {
-    let (codetag, comment) = read_codetag(bread)?;
-    let mut character = read_character(bread, header)?;
-    character.comment = comment.map(String::into_boxed_str);
+/// A FIGcharacter of a font. Lazily parsed fonts only index where its rows
+/// are, and parse it on first access.
+#[derive(Debug, Clone)]
+pub(crate) struct Glyph {
+    character: OnceLock<FIGcharacter>,
+    rows: Range<usize>,
+    comment: Option<Box<str>>,
+}
+
+impl Glyph {
+    /// Parse the FIGcharacter for `code`. If lazy, only check its rows.
+    pub(crate) fn parse<R: Read>(
+        reader: &mut LineReader<R>,
+        header: &Header,
+        code: i32,
+        lazy: bool,
+    ) -> Result<Glyph> {
+        read_glyph(reader, header, Some(code), None, lazy)
+    }
+
+    /// Parse a codetagged FIGcharacter. If lazy, only check its rows. When
+    /// not strict, the ones with an invalid codetag are skipped.
+    pub(crate) fn parse_with_codetag<R: Read>(
+        reader: &mut LineReader<R>,
+        header: &Header,
+        lazy: bool,
+    ) -> Result<Option<(i32, Glyph)>> {
+        let (code, comment) = read_codetag(reader, header.encoding())?;
+        let glyph = read_glyph(reader, header, code, comment, lazy)?;
+
+        Ok(code.map(|code| (code, glyph)))
+    }
 
-    Ok((codetag, character))
+    /// Get the FIGcharacter, parsing it from the font file if needed.
+    pub(crate) fn get(&self, source: &[u8], header: &Header, code: i32) -> &FIGcharacter {
+        self.character.get_or_init(|| {
+            parse_lazily(
+                &source[self.rows.clone()],
+                header,
+                code,
+                self.comment.clone(),
+            )
+        })
+    }
+
+    /// Take the FIGcharacter, parsing it from the font file if needed.
+    pub(crate) fn into_character(self, source: &[u8], header: &Header, code: i32) -> FIGcharacter {
+        let Glyph {
+            character,
+            rows,
+            comment,
+        } = self;
+
+        character
+            .into_inner()
+            .unwrap_or_else(|| parse_lazily(&source[rows], header, code, comment))
+    }
 }
 
-fn read_codetag<R: Read>(bread: &mut BufReader<R>) -> Result<(i32, Option<String>)> {
-    let line = read_line(bread)?;
-    let mut line = line.splitn(2, |c| c == &b' ');
-    let mut code = line.next().ok_or(ParseError::InvalidCharacter)?;
-    let comment = match line.next() {
-        Some(bytes) => match ISO_8859_1.decode(bytes, DecoderTrap::Replace) {
-            Ok(comment) => Some(comment),
-            Err(_) => None,
-        },
-        None => None,
-    };
+impl From<FIGcharacter> for Glyph {
+    #[inline]
+    fn from(character: FIGcharacter) -> Self {
+        Glyph {
+            character: OnceLock::from(character),
+            rows: 0..0,
+            comment: None,
+        }
+    }
+}
 
-    let sign: i32 = if code.starts_with(b"-") {
-        code = &code[1..];
-        -1
-    } else {
-        1
-    };
+fn read_glyph<R: Read>(
+    reader: &mut LineReader<R>,
+    header: &Header,
+    code: Option<i32>,
+    comment: Option<String>,
+    lazy: bool,
+) -> Result<Glyph> {
+    let codetag = code.map(Codetag::from);
+    let comment = comment.map(String::into_boxed_str);
+
+    if !lazy {
+        let mut character = read_character(reader, header, codetag)?;
+        character.comment = comment;
+        return Ok(Glyph::from(character));
+    }
 
-    let code = if code.starts_with(b"0x") || code.starts_with(b"0X") {
-        let code = from_utf8(&code[2..]).map_err(|_| ParseError::InvalidCharacter)?;
-        i32::from_str_radix(code, 16)
-    } else if code.starts_with(b"0") {
-        let code = from_utf8(&code[1..]).map_err(|_| ParseError::InvalidCharacter)?;
-        i32::from_str_radix(code, 8)
-    } else {
-        from_utf8(code)
-            .map_err(|_| ParseError::InvalidCharacter)?
-            .parse()
-    };
+    // Check the rows like `read_character` does, without keeping them.
+    let (blank, encoding) = (header.hard_blank_char(), header.encoding());
+    let (_, start) = reader.position();
+    read_rows(reader, header, codetag, |reader, (line, offset), row| {
+        if !SubCharacter::is_valid(row, blank, encoding) {
+            let kind = ParseErrorKind::InvalidEncoding(encoding);
+            reader.warn(ParseError::new(kind, line, offset).in_character(codetag))?;
+        }
+        Ok(())
+    })?;
+    let (_, end) = reader.position();
 
-    Ok((
-        code.map_err(|_| ParseError::InvalidCharacter)? * sign,
+    Ok(Glyph {
+        character: OnceLock::new(),
+        rows: start as usize..end as usize,
         comment,
-    ))
+    })
 }
 
-fn read_character<R: Read>(bread: &mut BufReader<R>, header: &Header) -> Result<FIGcharacter> {
-    let mut lines = read_lines(bread, header.height())?;
-
-    let first = &lines[0];
+/// Parse the rows of a lazily parsed FIGcharacter. Their problems were
+/// already reported while indexing them, so this can't fail: when not strict,
+/// what isn't valid in the font's encoding is replaced like `read_character`
+/// does.
+fn parse_lazily(
+    rows: &[u8],
+    header: &Header,
+    code: i32,
+    comment: Option<Box<str>>,
+) -> FIGcharacter {
+    let mut reader = LineReader::new(rows, false);
+    let mut character = read_character(&mut reader, header, Some(Codetag::from(code)))
+        .expect("FIGcharacter rows were already read while indexing");
+    character.comment = comment;
+    character
+}
 
-    if first.is_empty() {
-        return Err(ParseError::InvalidCharacter.into());
+fn read_codetag<R: Read>(
+    reader: &mut LineReader<R>,
+    encoding: Encoding,
+) -> Result<(Option<i32>, Option<String>)> {
+    let error = reader.error(ParseErrorKind::InvalidCodetag);
+    let line = reader.read_line()?;
+    let mut line = line.splitn(2, |c| c == &b' ');
+    let code = line.next().and_then(parse_codetag);
+    if code.is_none() {
+        reader.warn(error)?;
     }
+    let comment = line.next().map(|bytes| encoding.decode_lossy(bytes));
 
-    let delimiter = *first.last().unwrap();
+    Ok((code, comment))
+}
 
-    {
-        let last_i = lines.len() - 1;
-        if !lines[last_i].ends_with(&[delimiter][..]) {
-            return Err(ParseError::InvalidCharacter.into());
-        }
+/// Parse a decimal, octal (`0` prefix) or hexadecimal (`0x` prefix)
+/// codetag, possibly negative.
+fn parse_codetag(code: &[u8]) -> Option<i32> {
+    let (sign, code) = match code.strip_prefix(b"-") {
+        Some(code) => (-1, code),
+        None => (1, code),
+    };
+    let code = from_utf8(code).ok()?;
+
+    // Parsed wider, for `-0x80000000`.
+    let code = if let Some(hex) = code.strip_prefix("0x").or(code.strip_prefix("0X")) {
+        i64::from_str_radix(hex, 16)
+    } else if code.len() > 1 && code.starts_with('0') {
+        i64::from_str_radix(&code[1..], 8)
+    } else {
+        code.parse()
+    };
+
+    code.ok().and_then(|code| i32::try_from(code * sign).ok())
+}
 
-        if lines[last_i].ends_with(&[delimiter, delimiter][..]) {
-            let new_len = lines[last_i].len() - 1;
-            unsafe { lines[last_i].set_len(new_len) };
+/// Read the rows of a FIGcharacter, passing each one without its endmark to
+/// `row`, with the line number and byte offset it starts at.
+fn read_rows<R, F>(
+    reader: &mut LineReader<R>,
+    header: &Header,
+    codetag: Option<Codetag>,
+    mut row: F,
+) -> Result<()>
+where
+    R: Read,
+    F: FnMut(&mut LineReader<R>, (usize, u64), &[u8]) -> Result<()>,
+{
+    let error = |(line, offset), kind| ParseError::new(kind, line, offset).in_character(codetag);
+
+    let height = header.height();
+    let mut buf = Vec::new();
+    // When not strict, rows without endmark are kept as they are, and the
+    // others lose their own endmark even if it differs from the first one.
+    let mut delimiter = None;
+    for i in 0..height {
+        let position = reader.position();
+        let last = i + 1 == height;
+        let read = if last {
+            reader.read_last_line_into(&mut buf)
+        } else {
+            reader.read_line_into(&mut buf)
+        };
+        read.map_err(|e| e.in_character(codetag))?;
+
+        let length = line_length(&buf, header.encoding());
+        if length > header.max_length() {
+            let kind = ParseErrorKind::LineTooLong {
+                length,
+                max_length: header.max_length(),
+            };
+            reader.lint(error(position, kind));
         }
-    }
 
-    for line in lines.iter_mut() {
-        if line.is_empty() {
-            return Err(ParseError::InvalidCharacter.into());
+        delimiter = delimiter.or(buf.last().copied());
+        if let Some(delimiter) = delimiter {
+            if last && buf.ends_with(&[delimiter, delimiter]) {
+                buf.pop();
+            }
         }
 
-        if *line.last().unwrap() != delimiter {
-            return Err(ParseError::InvalidCharacter.into());
+        match (buf.pop(), delimiter) {
+            (None, _) => reader.warn(error(position, ParseErrorKind::MissingEndmark))?,
+            (Some(found), Some(expected)) if found != expected => {
+                let kind = ParseErrorKind::InconsistentEndmark {
+                    expected: char::from(expected),
+                    found: char::from(found),
+                };
+                reader.warn(error(position, kind).at_column(buf.len() + 1))?;
+            }
+            _ => (),
         }
 
-        let len = line.len();
-        line.truncate(len - 1);
+        row(reader, position, &buf)?;
     }
 
-    let mut res: Vec<Vec<SubCharacter>> = Vec::with_capacity(lines.len());
+    Ok(())
+}
 
-    for line in lines {
-        res.push(
-            SubCharacter::split(&line[..], header.hard_blank_char())
-                .ok()
-                .ok_or::<Error>(ParseError::InvalidCharacter.into())?,
-        );
+fn read_character<R: Read>(
+    reader: &mut LineReader<R>,
+    header: &Header,
+    codetag: Option<Codetag>,
+) -> Result<FIGcharacter> {
+    let error = |(line, offset), kind| ParseError::new(kind, line, offset).in_character(codetag);
+
+    let mut rows = Vec::with_capacity(header.height());
+    read_rows(reader, header, codetag, |_, position, row| {
+        rows.push((position, row.to_vec()));
+        Ok(())
+    })?;
+    let mut res: Vec<Vec<SubCharacter>> = Vec::with_capacity(rows.len());
+    let mut width = None;
+
+    for (position, row) in rows {
+        let blank = header.hard_blank_char();
+        let line = match SubCharacter::split_with(&row, blank, header.encoding()) {
+            Ok(line) => line,
+            Err(kind) => {
+                reader.warn(error(position, kind))?;
+                SubCharacter::split_lossy(&row, blank, header.encoding())
+            }
+        };
+
+        let found = line.len();
+        match width {
+            None => width = Some(found),
+            Some(expected) if expected != found => {
+                let kind = ParseErrorKind::InconsistentWidth { expected, found };
+                reader.lint(error(position, kind));
+            }
+            _ => (),
+        }
+
+        res.push(line);
     }
 
-    let max_len = res
-        .iter()
-        .map(|line| line.iter().map(|c| c.width()).sum())
-        .max()
-        .unwrap_or(0);
+    Ok(FIGcharacter {
+        comment: None,
+        lines: pad_lines(res),
+    })
+}
 
-    let res = res
+fn pad_lines(lines: Vec<Vec<SubCharacter>>) -> Box<[Box<[SubCharacter]>]> {
+    let max_len = lines.iter().map(|line| line.len()).max().unwrap_or(0);
+
+    lines
         .into_iter()
         .map(|mut line| {
             if line.len() < max_len {
@@ -167,22 +390,56 @@ fn read_character<R: Read>(bread: &mut BufReader<R>, header: &Header) -> Result<
             }
             line.into_boxed_slice()
         })
-        .collect();
-
-    Ok(FIGcharacter {
-        comment: None,
-        lines: res,
-    })
+        .collect()
 }
 
-fn read_lines<R: Read>(bread: &mut BufReader<R>, num: usize) -> Result<Vec<Vec<u8>>> {
-    let mut lines = Vec::with_capacity(num);
+/// Endmarks to try first, the rest of printable ASCII follows.
+const ENDMARKS: &[u8] = b"@#$%&*!|+=~^:;";
 
-    for _ in 0..(num - 1) {
-        lines.push(read_line(bread)?);
+/// Get the length of a line as figlet counts it: in characters for UTF-8
+/// fonts and in bytes otherwise.
+pub(crate) fn line_length(line: &[u8], encoding: Encoding) -> usize {
+    match encoding {
+        Encoding::Utf8 => line.iter().filter(|b| **b & 0xc0 != 0x80).count(),
+        _ => line.len(),
     }
+}
 
-    lines.push(read_last_line(bread)?);
+fn encode_character(character: &FIGcharacter, header: &Header) -> Result<Vec<Vec<u8>>> {
+    let blank = header.hard_blank_char();
+
+    let mut lines: Vec<Vec<u8>> = Vec::with_capacity(header.height());
+    for i in 0..header.height() {
+        let mut bytes = Vec::new();
+        for sub in character.lines.get(i).iter().flat_map(|line| line.iter()) {
+            match sub {
+                SubCharacter::Blank => bytes.extend_from_slice(blank),
+                SubCharacter::Symbol(sym) => {
+                    let sym = header.encoding().encode(sym)?;
+                    if sym.windows(blank.len()).any(|window| window == blank) {
+                        return Err(Error::HardBlankSymbol);
+                    }
+                    bytes.extend(sym);
+                }
+            }
+        }
+        lines.push(bytes);
+    }
+
+    let endmark = ENDMARKS
+        .iter()
+        .copied()
+        .chain(b'!'..=b'~')
+        .find(|c| !blank.contains(c) && lines.iter().all(|line| !line.contains(c)))
+        .ok_or(Error::NoEndmark)?;
+
+    let last = lines.len().saturating_sub(1);
+    for (i, line) in lines.iter_mut().enumerate() {
+        line.push(endmark);
+        if i == last {
+            line.push(endmark);
+        }
+    }
 
     Ok(lines)
 }
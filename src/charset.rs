@@ -0,0 +1,169 @@
+use std::borrow::Cow;
+
+use encoding::{
+    all::{ISO_8859_1, UTF_8, WINDOWS_1252},
+    codec::singlebyte::SingleByteEncoding,
+    DecoderTrap, EncoderTrap, EncodingRef,
+};
+
+use crate::{error::Error, header::FontFormat, result::Result};
+
+/// The character encoding of a font file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Encoding {
+    /// ISO-8859-1 for FIGlet fonts and UTF-8 for TOIlet fonts. When reading,
+    /// FIGlet fonts are UTF-8 too if they are valid UTF-8 with non-ASCII
+    /// characters: checking it reads the whole font in memory first.
+    #[default]
+    Auto,
+    /// ISO-8859-1, the encoding mandated by the FIGfont specification.
+    Latin1,
+    /// UTF-8, the encoding of TOIlet fonts.
+    Utf8,
+    /// IBM code page 437, the original IBM PC character set.
+    Cp437,
+    /// Windows-1252.
+    Windows1252,
+}
+
+impl Encoding {
+    /// Pick the encoding of a whole font file. ASCII FIGlet fonts are
+    /// ISO-8859-1, like the specification says.
+    pub(crate) fn detect(bytes: &[u8]) -> Encoding {
+        if bytes.starts_with(FontFormat::Toilet.magic_number())
+            || (!bytes.is_ascii() && std::str::from_utf8(bytes).is_ok())
+        {
+            Encoding::Utf8
+        } else {
+            Encoding::Latin1
+        }
+    }
+
+    fn codec(self, bytes: &[u8]) -> EncodingRef {
+        match self {
+            Encoding::Auto => Encoding::detect(bytes).codec(bytes),
+            Encoding::Latin1 => ISO_8859_1,
+            Encoding::Utf8 => UTF_8,
+            Encoding::Cp437 => &CP437,
+            Encoding::Windows1252 => WINDOWS_1252,
+        }
+    }
+
+    /// Decode `bytes`, failing on invalid sequences.
+    pub(crate) fn decode(self, bytes: &[u8]) -> std::result::Result<String, Cow<'static, str>> {
+        self.codec(bytes).decode(bytes, DecoderTrap::Strict)
+    }
+
+    /// Check if `bytes` decode without invalid sequences.
+    pub(crate) fn is_valid(self, bytes: &[u8]) -> bool {
+        match self {
+            Encoding::Latin1 | Encoding::Cp437 => true,
+            Encoding::Utf8 => std::str::from_utf8(bytes).is_ok(),
+            encoding => encoding.decode(bytes).is_ok(),
+        }
+    }
+
+    /// Encode `text`, failing on the characters the encoding can't represent.
+    pub(crate) fn encode(self, text: &str) -> Result<Vec<u8>> {
+        let codec = match self {
+            Encoding::Auto => UTF_8,
+            encoding => encoding.codec(&[]),
+        };
+
+        codec.encode(text, EncoderTrap::Strict).map_err(|_| {
+            let unsupported: Vec<char> = text
+                .chars()
+                .filter(|c| codec.encode(&c.to_string(), EncoderTrap::Strict).is_err())
+                .collect();
+            Error::UnsupportedCharacters(unsupported.into_boxed_slice())
+        })
+    }
+
+    /// Decode `bytes`, replacing invalid sequences.
+    pub(crate) fn decode_lossy(self, bytes: &[u8]) -> String {
+        self.codec(bytes)
+            .decode(bytes, DecoderTrap::Replace)
+            .unwrap_or_default()
+    }
+}
+
+static CP437: SingleByteEncoding = SingleByteEncoding {
+    name: "ibm437",
+    whatwg_name: None,
+    index_forward: cp437_forward,
+    index_backward: cp437_backward,
+};
+
+const CP437_TABLE: [u16; 128] = [
+    0x00C7, 0x00FC, 0x00E9, 0x00E2, 0x00E4, 0x00E0, 0x00E5, 0x00E7, 0x00EA, 0x00EB, 0x00E8, 0x00EF,
+    0x00EE, 0x00EC, 0x00C4, 0x00C5, 0x00C9, 0x00E6, 0x00C6, 0x00F4, 0x00F6, 0x00F2, 0x00FB, 0x00F9,
+    0x00FF, 0x00D6, 0x00DC, 0x00A2, 0x00A3, 0x00A5, 0x20A7, 0x0192, 0x00E1, 0x00ED, 0x00F3, 0x00FA,
+    0x00F1, 0x00D1, 0x00AA, 0x00BA, 0x00BF, 0x2310, 0x00AC, 0x00BD, 0x00BC, 0x00A1, 0x00AB, 0x00BB,
+    0x2591, 0x2592, 0x2593, 0x2502, 0x2524, 0x2561, 0x2562, 0x2556, 0x2555, 0x2563, 0x2551, 0x2557,
+    0x255D, 0x255C, 0x255B, 0x2510, 0x2514, 0x2534, 0x252C, 0x251C, 0x2500, 0x253C, 0x255E, 0x255F,
+    0x255A, 0x2554, 0x2569, 0x2566, 0x2560, 0x2550, 0x256C, 0x2567, 0x2568, 0x2564, 0x2565, 0x2559,
+    0x2558, 0x2552, 0x2553, 0x256B, 0x256A, 0x2518, 0x250C, 0x2588, 0x2584, 0x258C, 0x2590, 0x2580,
+    0x03B1, 0x00DF, 0x0393, 0x03C0, 0x03A3, 0x03C3, 0x00B5, 0x03C4, 0x03A6, 0x0398, 0x03A9, 0x03B4,
+    0x221E, 0x03C6, 0x03B5, 0x2229, 0x2261, 0x00B1, 0x2265, 0x2264, 0x2320, 0x2321, 0x00F7, 0x2248,
+    0x00B0, 0x2219, 0x00B7, 0x221A, 0x207F, 0x00B2, 0x25A0, 0x00A0,
+];
+
+fn cp437_forward(code: u8) -> u16 {
+    CP437_TABLE[(code - 0x80) as usize]
+}
+
+fn cp437_backward(code: u32) -> u8 {
+    CP437_TABLE
+        .iter()
+        .position(|c| *c as u32 == code)
+        .map_or(0, |i| i as u8 + 0x80)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Encoding;
+    use crate::error::Error;
+
+    #[test]
+    fn decode() {
+        let bytes = b"\x80\xb0\xdb\xe1";
+
+        assert_eq!(
+            Encoding::Latin1.decode(bytes).unwrap(),
+            "\u{80}\u{b0}\u{db}\u{e1}"
+        );
+        assert_eq!(
+            Encoding::Cp437.decode(bytes).unwrap(),
+            "\u{c7}\u{2591}\u{2588}\u{df}"
+        );
+        assert_eq!(
+            Encoding::Windows1252.decode(bytes).unwrap(),
+            "\u{20ac}\u{b0}\u{db}\u{e1}"
+        );
+        assert!(Encoding::Utf8.decode(bytes).is_err());
+        assert_eq!(Encoding::Utf8.decode_lossy(b"a\xff"), "a\u{fffd}");
+
+        assert_eq!(Encoding::detect(b"flf2a \xe9"), Encoding::Latin1);
+        assert_eq!(Encoding::detect("flf2a \u{e9}".as_bytes()), Encoding::Utf8);
+        assert_eq!(Encoding::detect(b"tlf2a \xe9"), Encoding::Utf8);
+        assert_eq!(Encoding::detect(b"flf2a a"), Encoding::Latin1);
+        assert_eq!(Encoding::detect(b"tlf2a a"), Encoding::Utf8);
+        assert_eq!(Encoding::Auto.decode(b"\xe9").unwrap(), "\u{e9}");
+        assert_eq!(
+            Encoding::Auto.decode("\u{e9}".as_bytes()).unwrap(),
+            "\u{e9}"
+        );
+    }
+
+    #[test]
+    fn encode() {
+        let text = "\u{c7}\u{2588}";
+
+        assert_eq!(Encoding::Cp437.encode(text).unwrap(), b"\x80\xdb");
+        assert_eq!(Encoding::Utf8.encode(text).unwrap(), text.as_bytes());
+        assert!(matches!(
+            Encoding::Latin1.encode(text),
+            Err(Error::UnsupportedCharacters(c)) if c[..] == ['\u{2588}']
+        ));
+    }
+}
@@ -0,0 +1,111 @@
+use std::{
+    convert::TryFrom,
+    fmt::{Display, Formatter},
+};
+
+/// The code of a FIGcharacter.
+///
+/// FIGfonts use signed 32 bits codes, only part of which are Unicode scalar
+/// values reachable from Rust's `char`s.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Codetag {
+    /// A Unicode scalar value.
+    Char(char),
+    /// A negative codetag. FIGfonts can define them but they can't be
+    /// reached from Unicode text.
+    Negative(i32),
+    /// A positive code that isn't a Unicode scalar value: a surrogate or a
+    /// value above U+10FFFF.
+    Invalid(i32),
+}
+
+impl Codetag {
+    /// Get the raw code.
+    pub fn code(self) -> i32 {
+        match self {
+            Codetag::Char(c) => c as i32,
+            Codetag::Negative(code) | Codetag::Invalid(code) => code,
+        }
+    }
+
+    /// Get the Unicode character, if the code is a Unicode scalar value.
+    pub fn as_char(self) -> Option<char> {
+        match self {
+            Codetag::Char(c) => Some(c),
+            _ => None,
+        }
+    }
+
+    /// Check if it is a negative codetag.
+    pub fn is_negative(self) -> bool {
+        matches!(self, Codetag::Negative(_))
+    }
+}
+
+impl From<i32> for Codetag {
+    fn from(code: i32) -> Self {
+        if code < 0 {
+            Codetag::Negative(code)
+        } else {
+            match char::from_u32(code as u32) {
+                Some(c) => Codetag::Char(c),
+                None => Codetag::Invalid(code),
+            }
+        }
+    }
+}
+
+impl From<char> for Codetag {
+    #[inline]
+    fn from(c: char) -> Self {
+        Codetag::Char(c)
+    }
+}
+
+impl From<Codetag> for i32 {
+    #[inline]
+    fn from(codetag: Codetag) -> Self {
+        codetag.code()
+    }
+}
+
+impl TryFrom<Codetag> for char {
+    type Error = Codetag;
+
+    fn try_from(codetag: Codetag) -> std::result::Result<Self, <Self as TryFrom<Codetag>>::Error> {
+        codetag.as_char().ok_or(codetag)
+    }
+}
+
+impl Display for Codetag {
+    fn fmt(&self, fmt: &mut Formatter<'_>) -> std::result::Result<(), std::fmt::Error> {
+        match self {
+            Codetag::Negative(code) => write!(fmt, "-0x{:04X}", -(*code as i64)),
+            _ => write!(fmt, "0x{:04X}", self.code()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Codetag;
+
+    #[test]
+    fn from_code() {
+        assert_eq!(Codetag::from(65), Codetag::Char('A'));
+        assert_eq!(Codetag::from(0), Codetag::Char('\0'));
+        assert_eq!(Codetag::from(-5), Codetag::Negative(-5));
+        assert_eq!(Codetag::from(i32::MIN), Codetag::Negative(i32::MIN));
+        assert_eq!(Codetag::from(0xD800), Codetag::Invalid(0xD800));
+        assert_eq!(Codetag::from(0x110000), Codetag::Invalid(0x110000));
+        assert_eq!(Codetag::from(i32::MAX).code(), i32::MAX);
+        assert_eq!(Codetag::from('\u{10FFFF}').code(), 0x10FFFF);
+    }
+
+    #[test]
+    fn display() {
+        assert_eq!(Codetag::Char('A').to_string(), "0x0041");
+        assert_eq!(Codetag::Negative(-5).to_string(), "-0x0005");
+        assert_eq!(Codetag::Negative(i32::MIN).to_string(), "-0x80000000");
+    }
+}
@@ -0,0 +1,454 @@
+use std::{
+    io::{BufReader, Read},
+    iter::Peekable,
+    path::Path,
+    str::Chars,
+};
+
+use encoding::{all::ISO_8859_1, DecoderTrap, Encoding};
+
+use crate::{
+    charset,
+    error::{Error, ParseError, ParseErrorKind},
+    result::Result,
+};
+
+const MAGIC_NUMBER: &str = "flc2a";
+
+/// How the input text is decoded. Only meaningful to programs decoding raw
+/// bytes: text given to the renderer is already Unicode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum InputMode {
+    /// ISO 2022 (the default).
+    #[default]
+    Iso2022,
+    /// HZ, `h` command.
+    Hz,
+    /// Shift-JIS, `j` command.
+    ShiftJis,
+    /// DBCS, `b` command.
+    Dbcs,
+    /// UTF-8, `u` command.
+    Utf8,
+}
+
+/// The size of an ISO 2022 character set.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CharsetSize {
+    /// A 94 characters set.
+    Single94,
+    /// A 96 characters set.
+    Single96,
+    /// A 94x94 characters set.
+    Double94,
+}
+
+/// A command of a FIGlet control file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Command {
+    /// Translate the codes in `start..=end` adding `offset` to them (`t`
+    /// command, or a pair of numbers).
+    Translate { start: i32, end: i32, offset: i32 },
+    /// Freeze the translations so far: the following ones are applied to
+    /// their output (`f` command).
+    Freeze,
+    /// Select the input decoding.
+    InputMode(InputMode),
+    /// Designate the character set with the final byte `final_byte` to
+    /// G`set` (`g0` to `g3` commands).
+    Designate {
+        set: u8,
+        size: CharsetSize,
+        final_byte: u8,
+    },
+    /// Invoke G`set` into the left half (`gL` command).
+    InvokeLeft(u8),
+    /// Invoke G`set` into the right half (`gR` command).
+    InvokeRight(u8),
+}
+
+/// FIGlet control file (.flc), remapping the input characters before they
+/// are looked up in the font.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ControlFile {
+    commands: Box<[Command]>,
+}
+
+impl ControlFile {
+    /// Read and parse a control file from a path. It can be zipped, gzipped
+    /// or zstd compressed if you have the matching feature enabled.
+    pub fn load_from<P: AsRef<Path>>(path: P) -> Result<ControlFile> {
+        load_from(path)
+    }
+
+    /// Read and parse a control file from a impl Read.
+    pub fn read_from<R: Read>(reader: R) -> Result<ControlFile> {
+        parse(reader)
+    }
+
+    /// Get the control file's commands.
+    pub fn commands(&self) -> &[Command] {
+        &self.commands
+    }
+
+    /// Get the input decoding selected by the control file.
+    pub fn input_mode(&self) -> InputMode {
+        self.commands
+            .iter()
+            .rev()
+            .find_map(|command| match command {
+                Command::InputMode(mode) => Some(*mode),
+                _ => None,
+            })
+            .unwrap_or_default()
+    }
+
+    /// Translate `code`. In every group of translations between two freezes
+    /// only the first matching one is applied.
+    pub fn translate(&self, code: i32) -> i32 {
+        let mut code = code;
+        let mut frozen = false;
+
+        for command in self.commands.iter() {
+            match *command {
+                Command::Freeze => frozen = false,
+                Command::Translate { start, end, offset }
+                    if !frozen && start <= code && code <= end =>
+                {
+                    code = code.wrapping_add(offset);
+                    frozen = true;
+                }
+                _ => (),
+            }
+        }
+
+        code
+    }
+}
+
+fn load_from<P: AsRef<Path>>(path: P) -> Result<ControlFile> {
+    let path = path.as_ref();
+    match path.extension() {
+        Some(ext) => {
+            if ext != "flc" {
+                return Err(Error::InvalidExtension);
+            }
+        }
+        None => {
+            return Err(Error::InvalidExtension);
+        }
+    }
+
+    crate::load_sniffed(path, &|f| parse(f))
+}
+
+fn parse<R: Read>(reader: R) -> Result<ControlFile> {
+    let mut bytes = Vec::new();
+    BufReader::new(reader).read_to_end(&mut bytes)?;
+
+    let (text, latin1) = match String::from_utf8(bytes) {
+        Ok(text) => (text, false),
+        Err(err) => (
+            ISO_8859_1
+                .decode(err.as_bytes(), DecoderTrap::Strict)
+                .map_err(|_| {
+                    let kind = ParseErrorKind::InvalidEncoding(charset::Encoding::Latin1);
+                    ParseError::new(kind, 1, 0)
+                })?,
+            true,
+        ),
+    };
+    // Latin1 characters are a byte each in the file.
+    let len = |text: &str| {
+        if latin1 {
+            text.chars().count()
+        } else {
+            text.len()
+        }
+    };
+
+    let mut commands = Vec::new();
+    let mut offset = 0;
+
+    for (i, raw) in text.split_inclusive('\n').enumerate() {
+        let line = raw.trim_start();
+        let error = ParseError::new(ParseErrorKind::InvalidCommand, i + 1, offset as u64)
+            .at_column(len(raw) - len(line) + 1);
+        offset += len(raw);
+
+        let line = line.trim_end_matches(['\n', '\r']);
+        if line.is_empty() || line.starts_with('#') || (i == 0 && line.starts_with(MAGIC_NUMBER)) {
+            continue;
+        }
+
+        commands.push(parse_command(line).ok_or(error)?);
+    }
+
+    Ok(ControlFile {
+        commands: commands.into_boxed_slice(),
+    })
+}
+
+fn parse_command(line: &str) -> Option<Command> {
+    let mut chars = line.chars().peekable();
+
+    Some(match chars.peek()? {
+        '0'..='9' | '-' => {
+            let from = read_number(&mut chars)?;
+            skip_whitespaces(&mut chars);
+            let to = read_number(&mut chars)?;
+            Command::Translate {
+                start: from,
+                end: from,
+                offset: to.wrapping_sub(from),
+            }
+        }
+        _ => match chars.next()? {
+            't' => {
+                skip_whitespaces(&mut chars);
+                let (start, end) = read_range(&mut chars)?;
+                skip_whitespaces(&mut chars);
+                let (to, to_end) = read_range(&mut chars)?;
+                if end.wrapping_sub(start) != to_end.wrapping_sub(to) {
+                    return None;
+                }
+                Command::Translate {
+                    start: start.min(end),
+                    end: start.max(end),
+                    offset: to.wrapping_sub(start),
+                }
+            }
+            'f' => Command::Freeze,
+            'h' => Command::InputMode(InputMode::Hz),
+            'j' => Command::InputMode(InputMode::ShiftJis),
+            'b' => Command::InputMode(InputMode::Dbcs),
+            'u' => Command::InputMode(InputMode::Utf8),
+            'g' => parse_designation(&mut chars)?,
+            _ => return None,
+        },
+    })
+}
+
+fn parse_designation(chars: &mut Peekable<Chars<'_>>) -> Option<Command> {
+    let set = |c: char| c.to_digit(4).map(|n| n as u8);
+
+    Some(match chars.next()? {
+        'L' | 'l' => {
+            skip_whitespaces(chars);
+            Command::InvokeLeft(set(chars.next()?)?)
+        }
+        'R' | 'r' => {
+            skip_whitespaces(chars);
+            Command::InvokeRight(set(chars.next()?)?)
+        }
+        c => {
+            let set = set(c)?;
+            skip_whitespaces(chars);
+            let size: String = chars.by_ref().take_while(|c| !c.is_whitespace()).collect();
+            let size = match size.as_str() {
+                "94" => CharsetSize::Single94,
+                "96" => CharsetSize::Single96,
+                "94x94" => CharsetSize::Double94,
+                _ => return None,
+            };
+            skip_whitespaces(chars);
+            let final_byte = chars.next()?;
+            if !final_byte.is_ascii() {
+                return None;
+            }
+            Command::Designate {
+                set,
+                size,
+                final_byte: final_byte as u8,
+            }
+        }
+    })
+}
+
+fn skip_whitespaces(chars: &mut Peekable<Chars<'_>>) {
+    while chars.peek().is_some_and(|c| c.is_whitespace()) {
+        chars.next();
+    }
+}
+
+/// Read a character or a range of characters like `a-z`.
+fn read_range(chars: &mut Peekable<Chars<'_>>) -> Option<(i32, i32)> {
+    let start = read_char(chars)?;
+    if chars.peek() != Some(&'-') {
+        return Some((start, start));
+    }
+    chars.next();
+
+    Some((start, read_char(chars)?))
+}
+
+/// Read a character specification: a literal character, an escape sequence
+/// or a backslash followed by a number.
+fn read_char(chars: &mut Peekable<Chars<'_>>) -> Option<i32> {
+    let c = chars.next()?;
+    if c != '\\' {
+        return Some(c as i32);
+    }
+
+    let code = match chars.peek()? {
+        '-' | '0'..='9' => return read_number(chars),
+        'a' => 7,
+        'b' => 8,
+        'e' => 27,
+        'f' => 12,
+        'n' => 10,
+        'r' => 13,
+        't' => 9,
+        'v' => 11,
+        c => *c as i32,
+    };
+    chars.next();
+
+    Some(code)
+}
+
+/// Read a decimal, octal (leading `0`) or hexadecimal (leading `0x`) number.
+fn read_number(chars: &mut Peekable<Chars<'_>>) -> Option<i32> {
+    let sign = if chars.peek() == Some(&'-') {
+        chars.next();
+        -1
+    } else {
+        1
+    };
+
+    let mut radix = 10;
+    if chars.peek() == Some(&'0') {
+        chars.next();
+        radix = 8;
+        if matches!(chars.peek(), Some('x') | Some('X')) {
+            chars.next();
+            radix = 16;
+        }
+    }
+
+    let mut digits = String::new();
+    while let Some(c) = chars.peek().filter(|c| c.is_digit(radix)) {
+        digits.push(*c);
+        chars.next();
+    }
+
+    if digits.is_empty() {
+        return if radix == 8 { Some(0) } else { None };
+    }
+
+    i64::from_str_radix(&digits, radix)
+        .ok()
+        .map(|n| (n * sign) as i32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CharsetSize, Command, ControlFile, InputMode};
+    use crate::error::{Error, ParseErrorKind};
+
+    #[test]
+    fn parse() {
+        let control = ControlFile::read_from(
+            &b"flc2a\n\
+              # comment\n\
+              \n\
+              t a-z A-Z\n\
+              t \\  _\n\
+              t \\0x41 \\66\n\
+              65 0101\n\
+              -2 -3\n\
+              f\n\
+              u\n\
+              g1 94x94 B\n\
+              gR 1\n"[..],
+        )
+        .unwrap();
+
+        assert_eq!(
+            control.commands(),
+            &[
+                Command::Translate {
+                    start: 97,
+                    end: 122,
+                    offset: -32,
+                },
+                Command::Translate {
+                    start: 32,
+                    end: 32,
+                    offset: 63,
+                },
+                Command::Translate {
+                    start: 65,
+                    end: 65,
+                    offset: 1,
+                },
+                Command::Translate {
+                    start: 65,
+                    end: 65,
+                    offset: 0,
+                },
+                Command::Translate {
+                    start: -2,
+                    end: -2,
+                    offset: -1,
+                },
+                Command::Freeze,
+                Command::InputMode(InputMode::Utf8),
+                Command::Designate {
+                    set: 1,
+                    size: CharsetSize::Double94,
+                    final_byte: b'B',
+                },
+                Command::InvokeRight(1),
+            ]
+        );
+        assert_eq!(control.input_mode(), InputMode::Utf8);
+
+        assert!(ControlFile::read_from(&b"flc2a\nz\n"[..]).is_err());
+        assert!(ControlFile::read_from(&b"flc2a\nt a\n"[..]).is_err());
+        assert!(ControlFile::read_from(&b"flc2a\nt a-z A-C\n"[..]).is_err());
+        assert!(ControlFile::read_from(&b"flc2a\nt a-c A\n"[..]).is_err());
+
+        match ControlFile::read_from(&b"flc2a\n# \xe9\n  z\n"[..]) {
+            Err(Error::Parse(error)) => {
+                assert_eq!(error.kind(), ParseErrorKind::InvalidCommand);
+                assert_eq!((error.line(), error.column(), error.offset()), (3, 3, 12));
+            }
+            result => panic!("expected a parse error, got {:?}", result),
+        }
+    }
+
+    #[test]
+    fn load_from() {
+        let upper = ControlFile::load_from("fonts/control/upper.flc").unwrap();
+        assert_eq!(upper.translate('a' as i32), 'A' as i32);
+
+        let utf8 = ControlFile::load_from("fonts/control/utf8.flc").unwrap();
+        assert_eq!(utf8.commands(), &[Command::InputMode(InputMode::Utf8)]);
+
+        #[cfg(feature = "zip")]
+        {
+            let lower = ControlFile::load_from("fonts/control/lower.flc").unwrap();
+            assert_eq!(lower.translate('A' as i32), 'a' as i32);
+        }
+
+        assert!(matches!(
+            ControlFile::load_from("fonts/plain/standard.flf"),
+            Err(Error::InvalidExtension)
+        ));
+    }
+
+    #[test]
+    fn translate() {
+        let control =
+            ControlFile::read_from(&b"flc2a\nt a-z A-Z\nt A B\nf\nt B C\nt C D\nt 3-1 c-a\n"[..])
+                .unwrap();
+
+        assert_eq!(control.translate('a' as i32), 'A' as i32);
+        assert_eq!(control.translate('A' as i32), 'C' as i32);
+        assert_eq!(control.translate('b' as i32), 'C' as i32);
+        assert_eq!(control.translate('c' as i32), 'D' as i32);
+        assert_eq!(control.translate('0' as i32), '0' as i32);
+        assert_eq!(control.translate('2' as i32), 'b' as i32);
+    }
+}
@@ -1,28 +1,65 @@
 #[cfg(feature = "zip")]
 use zip::result::ZipError;
 
+use crate::{charset::Encoding, codetag::Codetag};
+
 #[derive(thiserror::Error, Debug)]
 pub enum Error {
-    #[error("parse error")]
+    #[error(transparent)]
     Parse(#[from] ParseError),
+    #[error("invalid extension")]
+    InvalidExtension,
+    #[error("invalid font: {0}")]
+    InvalidFont(&'static str),
+    #[error("unsupported characters: {0:?}")]
+    UnsupportedCharacters(Box<[char]>),
+    #[error("invalid header: {0}")]
+    InvalidHeader(&'static str),
+    #[error("FIGcharacter {codetag} has {height} lines, expected {expected}")]
+    InvalidCharacterHeight {
+        codetag: Codetag,
+        height: usize,
+        expected: usize,
+    },
+    #[error("no endmark left for a FIGcharacter")]
+    NoEndmark,
+    #[error("a FIGcharacter has a symbol written like the hard blank")]
+    HardBlankSymbol,
     #[error("failed to read file")]
-    #[cfg(not(feature = "zip"))]
+    #[cfg(not(any(feature = "zip", feature = "gzip", feature = "zstd")))]
     Io(#[from] std::io::Error),
     #[error("failed to read file")]
-    #[cfg(feature = "zip")]
+    #[cfg(any(feature = "zip", feature = "gzip", feature = "zstd"))]
     Io(#[from] Io),
 }
 
-#[cfg(feature = "zip")]
+impl Error {
+    /// Set the FIGcharacter being parsed, for parse errors.
+    pub(crate) fn in_character(self, codetag: Option<Codetag>) -> Self {
+        match self {
+            Error::Parse(error) => Error::Parse(error.in_character(codetag)),
+            error => error,
+        }
+    }
+}
+
+#[cfg(any(feature = "zip", feature = "gzip", feature = "zstd"))]
 #[derive(thiserror::Error, Debug)]
 pub enum Io {
     #[error("failed to read file")]
     Std(#[from] std::io::Error),
     #[error("failed to read zip archive")]
+    #[cfg(feature = "zip")]
     Zip(#[from] ZipError),
+    #[error("failed to decompress gzip data")]
+    #[cfg(feature = "gzip")]
+    Gzip(std::io::Error),
+    #[error("failed to decompress zstd data")]
+    #[cfg(feature = "zstd")]
+    Zstd(std::io::Error),
 }
 
-#[cfg(feature = "zip")]
+#[cfg(any(feature = "zip", feature = "gzip", feature = "zstd"))]
 impl From<std::io::Error> for Error {
     fn from(inner: std::io::Error) -> Self {
         Error::Io(Io::Std(inner))
@@ -36,16 +73,115 @@ impl From<ZipError> for Error {
     }
 }
 
-#[derive(thiserror::Error, Debug, Clone, Copy)]
-pub enum ParseError {
-    #[error("not enough data")]
-    NotEnoughData,
-    #[error("invalid header")]
-    InvalidHeader,
-    #[error("invalid character")]
-    InvalidCharacter,
-    #[error("invalid font")]
-    InvalidFont,
-    #[error("invalid extension")]
-    InvalidExtension,
+/// An error parsing a font or a control file, with where it happened.
+#[derive(thiserror::Error, Debug, Clone, Copy, PartialEq, Eq)]
+#[error("{kind} at line {line}, column {column}{}", in_character(.codetag))]
+pub struct ParseError {
+    kind: ParseErrorKind,
+    line: usize,
+    column: usize,
+    offset: u64,
+    codetag: Option<Codetag>,
+}
+
+impl ParseError {
+    /// Create an error at the start of `line`, which starts at byte `offset`.
+    pub(crate) fn new(kind: ParseErrorKind, line: usize, offset: u64) -> Self {
+        ParseError {
+            kind,
+            line,
+            column: 1,
+            offset,
+            codetag: None,
+        }
+    }
+
+    /// Move the error to the byte `column` of its line, counting from 1.
+    pub(crate) fn at_column(mut self, column: usize) -> Self {
+        self.offset += (column - self.column) as u64;
+        self.column = column;
+        self
+    }
+
+    /// Set the FIGcharacter being parsed, if known.
+    pub(crate) fn in_character(mut self, codetag: Option<Codetag>) -> Self {
+        self.codetag = codetag;
+        self
+    }
+
+    /// Get what went wrong.
+    pub fn kind(&self) -> ParseErrorKind {
+        self.kind
+    }
+
+    /// Get the line, counting from 1.
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    /// Get the byte column in the line, counting from 1.
+    pub fn column(&self) -> usize {
+        self.column
+    }
+
+    /// Get the byte offset from the start of the data.
+    pub fn offset(&self) -> u64 {
+        self.offset
+    }
+
+    /// Get the codetag of the FIGcharacter being parsed, if any.
+    pub fn codetag(&self) -> Option<Codetag> {
+        self.codetag
+    }
+}
+
+fn in_character(codetag: &Option<Codetag>) -> String {
+    codetag
+        .map(|codetag| format!(" in FIGcharacter {}", codetag))
+        .unwrap_or_default()
+}
+
+/// Why a font or a control file couldn't be parsed.
+#[derive(thiserror::Error, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParseErrorKind {
+    #[error("unexpected end of file")]
+    UnexpectedEof,
+    #[error("missing newline at the end of the line")]
+    MissingNewline,
+    #[error("missing flf2a or tlf2a signature")]
+    InvalidSignature,
+    #[error("expected 6 to 9 header fields, found {0}")]
+    HeaderFieldCount(usize),
+    #[error("missing hard blank")]
+    MissingHardBlank,
+    /// Numeric header field N (1 for the height, 2 for the baseline and so
+    /// on) is not a valid number.
+    #[error("bad number in header field {0}")]
+    InvalidNumber(usize),
+    #[error("invalid codetag")]
+    InvalidCodetag,
+    #[error("missing endmark")]
+    MissingEndmark,
+    #[error("inconsistent endmark: expected {expected:?}, found {found:?}")]
+    InconsistentEndmark { expected: char, found: char },
+    #[error("invalid {0:?} data")]
+    InvalidEncoding(Encoding),
+    #[error("expected {expected} codetagged FIGcharacters, found {found}")]
+    CodetagCount { expected: u32, found: u32 },
+    #[error("invalid control file command")]
+    InvalidCommand,
+    #[error("missing required FIGcharacter")]
+    MissingCharacter,
+
+    // Only reported when validating a font.
+    #[error("baseline {baseline} is not between 1 and the height {height}")]
+    InvalidBaseline { baseline: usize, height: usize },
+    #[error("full layout {full_layout} contradicts old layout {old_layout}")]
+    LayoutMismatch { old_layout: i32, full_layout: u32 },
+    #[error("line is {length} characters long, more than the max length {max_length}")]
+    LineTooLong { length: usize, max_length: usize },
+    #[error("row is {found} SubCharacters wide, expected {expected}")]
+    InconsistentWidth { expected: usize, found: usize },
+    #[error("duplicate codetag")]
+    DuplicateCodetag,
 }
@@ -0,0 +1,148 @@
+use std::{
+    fmt::{Display, Formatter},
+    slice,
+};
+
+use crate::subcharacter::SubCharacter;
+
+/// A FIGure is a rendered text: a grid of SubCharacters.
+///
+/// Hard blanks are kept as `SubCharacter::Blank` and only resolved to spaces
+/// when the FIGure is printed or converted to strings.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FIGure {
+    rows: Vec<Vec<SubCharacter>>,
+}
+
+impl FIGure {
+    pub(crate) fn new(rows: Vec<Vec<SubCharacter>>) -> Self {
+        FIGure { rows }
+    }
+
+    /// Get the width (number of SubCharacters of the longest row) of the
+    /// FIGure.
+    pub fn width(&self) -> usize {
+        self.rows.iter().map(|x| x.len()).max().unwrap_or_default()
+    }
+
+    /// Get the height (number of rows) of the FIGure.
+    pub fn height(&self) -> usize {
+        self.rows.len()
+    }
+
+    /// Check if the FIGure has no rows.
+    pub fn is_empty(&self) -> bool {
+        self.rows.is_empty()
+    }
+
+    /// Get the row at `index`, if any.
+    pub fn row(&self, index: usize) -> Option<&[SubCharacter]> {
+        self.rows.get(index).map(AsRef::as_ref)
+    }
+
+    /// Iterate over the rows of SubCharacters.
+    pub fn rows(&self) -> Rows<'_> {
+        Rows {
+            inner: self.rows.iter(),
+        }
+    }
+
+    /// Convert the FIGure into a line of text per row, resolving hard blanks
+    /// to spaces.
+    pub fn to_lines(&self) -> Vec<String> {
+        self.rows().map(row_to_string).collect()
+    }
+}
+
+/// Iterator over the rows of a FIGure.
+#[derive(Debug, Clone)]
+pub struct Rows<'a> {
+    inner: slice::Iter<'a, Vec<SubCharacter>>,
+}
+
+impl<'a> Iterator for Rows<'a> {
+    type Item = &'a [SubCharacter];
+
+    fn next(&mut self) -> Option<<Self as Iterator>::Item> {
+        self.inner.next().map(AsRef::as_ref)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl DoubleEndedIterator for Rows<'_> {
+    fn next_back(&mut self) -> Option<<Self as Iterator>::Item> {
+        self.inner.next_back().map(AsRef::as_ref)
+    }
+}
+
+impl ExactSizeIterator for Rows<'_> {}
+
+impl<'a> IntoIterator for &'a FIGure {
+    type Item = &'a [SubCharacter];
+    type IntoIter = Rows<'a>;
+
+    fn into_iter(self) -> <Self as IntoIterator>::IntoIter {
+        self.rows()
+    }
+}
+
+impl Display for FIGure {
+    fn fmt(&self, fmt: &mut Formatter<'_>) -> std::result::Result<(), std::fmt::Error> {
+        for (i, row) in self.rows().enumerate() {
+            if i != 0 {
+                writeln!(fmt)?;
+            }
+
+            for sub in row {
+                write!(fmt, "{}", sub)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl From<FIGure> for Vec<String> {
+    #[inline]
+    fn from(figure: FIGure) -> Self {
+        figure.to_lines()
+    }
+}
+
+impl From<&FIGure> for Vec<String> {
+    #[inline]
+    fn from(figure: &FIGure) -> Self {
+        figure.to_lines()
+    }
+}
+
+fn row_to_string(row: &[SubCharacter]) -> String {
+    row.iter().map(AsRef::<str>::as_ref).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{FIGfont, SubCharacter};
+
+    #[test]
+    fn figure() {
+        let font = FIGfont::standard().unwrap();
+        let figure = font.render("Hi !").unwrap();
+
+        assert_eq!(figure.height(), 6);
+        assert_eq!(figure.width(), 13);
+        assert_eq!(figure.rows().len(), 6);
+        assert!(figure.rows().all(|row| row.len() == 13));
+        assert!(figure.row(6).is_none());
+
+        assert!(figure.rows().any(|row| row.contains(&SubCharacter::Blank)));
+
+        let lines: Vec<String> = figure.to_lines();
+        assert!(lines.iter().all(|line| !line.contains('$')));
+        assert_eq!(lines.join("\n"), figure.to_string());
+        assert_eq!(Vec::<String>::from(&figure), lines);
+    }
+}
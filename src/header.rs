@@ -1,18 +1,20 @@
 use std::{
     borrow::Cow,
-    io::{BufRead, BufReader, Read},
+    io::{Read, Write},
     str::{from_utf8, FromStr},
 };
 
 use crate::{
-    error::{Error, ParseError},
+    charset::Encoding,
+    error::{Error, ParseError, ParseErrorKind},
     result::Result,
-    utils::read_line,
+    utils::LineReader,
 };
 
 use bitflags::bitflags;
 
 const MAGIC_NUMBER: &[u8] = b"flf2a";
+const TOILET_MAGIC_NUMBER: &[u8] = b"tlf2a";
 
 bitflags! {
     /// The FIGfont's layout informations.
@@ -35,44 +37,87 @@ bitflags! {
         /// Apply smushing.
         const HORIZONTAL_SMUSH = 128;
 
+        /// Vertical equals smushing.
         const VERTICAL_EQUAL = 256;
+        /// Vertical underscore smushing.
         const VERTICAL_LOWLINE = 512;
+        /// Vertical hierarchy smushing.
         const VERTICAL_HIERARCHY = 1024;
+        /// Horizontal line smushing.
         const VERTICAL_PAIR = 2048;
+        /// Vertical line supersmushing.
         const VERTICAL_BIGX = 4096;
+        /// Apply vertical fitting.
         const VERTICAL_KERNING = 8192;
-        const VERTICAL_SMUSH = 16392;
+        /// Apply vertical smushing.
+        const VERTICAL_SMUSH = 16384;
     }
 }
 
 impl FromStr for Layout {
-    type Err = Error;
+    type Err = ParseErrorKind;
 
     fn from_str(raw: &str) -> std::result::Result<Self, <Self as FromStr>::Err> {
-        let raw: u32 = raw
-            .parse()
-            .ok()
-            .ok_or::<Error>(ParseError::InvalidHeader.into())?;
-        Layout::from_bits(raw).ok_or(ParseError::InvalidHeader.into())
+        let raw: u32 = raw.parse().map_err(|_| ParseErrorKind::InvalidNumber(7))?;
+        Ok(Layout::from_bits_retain(raw))
+    }
+}
+
+impl Layout {
+    /// Convert an old layout (the header's fifth parameter) to a layout: `-1`
+    /// is full width, `0` is kerning and positive values are the horizontal
+    /// smushing rules.
+    pub fn from_old_layout(old_layout: i32) -> Layout {
+        use std::cmp::Ordering::*;
+
+        match old_layout.cmp(&0) {
+            Equal => Layout::HORIZONTAL_KERNING,
+            Less => Layout::empty(),
+            Greater => Layout::from_bits_retain(old_layout as u32 & 31) | Layout::HORIZONTAL_SMUSH,
+        }
+    }
+
+    /// Convert the layout to an old layout. Vertical layouts and universal
+    /// smushing have no old layout counterpart: the latter becomes kerning.
+    pub fn to_old_layout(self) -> i32 {
+        if self.contains(Layout::HORIZONTAL_SMUSH) {
+            (self.bits() & 63) as i32
+        } else if self.contains(Layout::HORIZONTAL_KERNING) {
+            0
+        } else {
+            -1
+        }
     }
 }
 
 /// FIGfont's header.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Header {
     hard_blank_char: Box<[u8]>,
     height: usize,
     baseline: usize,
     max_length: usize,
-    layout: Layout,
+    old_layout: i32,
+    full_layout: Option<u32>,
     comment: Box<str>,
     print_direction: PrintDirection,
     codetag_count: Option<u32>,
+    format: FontFormat,
+    encoding: Encoding,
 }
 
 impl Header {
-    pub(crate) fn parse<R: Read>(bread: &mut BufReader<R>) -> Result<Header> {
-        parse_header(bread)
+    pub(crate) fn parse<R: Read>(reader: &mut LineReader<R>, encoding: Encoding) -> Result<Header> {
+        parse_header(reader, encoding)
+    }
+
+    pub(crate) fn write<W: Write>(
+        &self,
+        writer: &mut W,
+        max_length: usize,
+        codetag_count: Option<u32>,
+    ) -> Result<()> {
+        write_header(self, writer, max_length, codetag_count)
     }
 
     /// Get the hard blank character.
@@ -95,9 +140,27 @@ impl Header {
         self.max_length
     }
 
-    /// Get the font's layout.
+    pub(crate) fn set_max_length(&mut self, max_length: usize) {
+        self.max_length = max_length;
+    }
+
+    /// Get the font's layout, from the full layout if the font has one or
+    /// else from the old layout. Unknown bits are kept.
     pub fn layout(&self) -> Layout {
-        self.layout
+        match self.full_layout {
+            Some(full_layout) => Layout::from_bits_retain(full_layout),
+            None => Layout::from_old_layout(self.old_layout),
+        }
+    }
+
+    /// Get the raw old layout.
+    pub fn old_layout(&self) -> i32 {
+        self.old_layout
+    }
+
+    /// Get the raw full layout, if the font has one.
+    pub fn full_layout(&self) -> Option<u32> {
+        self.full_layout
     }
 
     /// Get the font's comment.
@@ -114,118 +177,336 @@ impl Header {
     pub fn codetag_count(&self) -> Option<u32> {
         self.codetag_count
     }
+
+    /// Get the format of the font.
+    pub fn format(&self) -> FontFormat {
+        self.format
+    }
+
+    /// Get the encoding the font was decoded with.
+    pub fn encoding(&self) -> Encoding {
+        self.encoding
+    }
+}
+
+/// Builder for a FIGfont's header.
+///
+/// ```
+/// use figfont::{HeaderBuilder, Layout};
+///
+/// let header = HeaderBuilder::new(3)
+///     .layout(Layout::HORIZONTAL_KERNING)
+///     .comment("A tiny font")
+///     .build()
+///     .unwrap();
+/// assert_eq!(header.height(), 3);
+/// assert_eq!(header.baseline(), 3);
+/// ```
+#[derive(Debug, Clone)]
+pub struct HeaderBuilder {
+    header: Header,
+}
+
+impl HeaderBuilder {
+    /// Create a builder for a font `height` lines tall, with `$` as hard
+    /// blank, full width layout and left to right print direction.
+    pub fn new(height: usize) -> Self {
+        HeaderBuilder {
+            header: Header {
+                hard_blank_char: Box::new(*b"$"),
+                height,
+                baseline: height,
+                max_length: 0,
+                old_layout: -1,
+                full_layout: Some(0),
+                comment: "".into(),
+                print_direction: PrintDirection::LeftToRight,
+                codetag_count: None,
+                format: FontFormat::Figlet,
+                encoding: Encoding::Auto,
+            },
+        }
+    }
+
+    /// Set the hard blank character.
+    pub fn hard_blank_char<B: AsRef<[u8]>>(mut self, hard_blank_char: B) -> Self {
+        self.header.hard_blank_char = hard_blank_char.as_ref().into();
+        self
+    }
+
+    /// Set the font's height (lines).
+    pub fn height(mut self, height: usize) -> Self {
+        self.header.height = height;
+        self
+    }
+
+    /// Set the font's baseline.
+    pub fn baseline(mut self, baseline: usize) -> Self {
+        self.header.baseline = baseline;
+        self
+    }
+
+    /// Set the font's max length. `FIGfontBuilder::build` raises it to what
+    /// the FIGcharacters need.
+    pub fn max_length(mut self, max_length: usize) -> Self {
+        self.header.max_length = max_length;
+        self
+    }
+
+    /// Set the font's layout, both the full layout and the old layout.
+    pub fn layout(mut self, layout: Layout) -> Self {
+        self.header.old_layout = layout.to_old_layout();
+        self.header.full_layout = Some(layout.bits());
+        self
+    }
+
+    /// Set the raw old layout, leaving the full layout untouched.
+    pub fn old_layout(mut self, old_layout: i32) -> Self {
+        self.header.old_layout = old_layout;
+        self
+    }
+
+    /// Set the raw full layout. Without it, the layout comes from the old
+    /// layout.
+    pub fn full_layout(mut self, full_layout: Option<u32>) -> Self {
+        self.header.full_layout = full_layout;
+        self
+    }
+
+    /// Set the font's comment.
+    pub fn comment<S: Into<String>>(mut self, comment: S) -> Self {
+        self.header.comment = comment.into().into_boxed_str();
+        self
+    }
+
+    /// Set the print direction.
+    pub fn print_direction(mut self, print_direction: PrintDirection) -> Self {
+        self.header.print_direction = print_direction;
+        self
+    }
+
+    /// Set the number of codetagged characters.
+    pub fn codetag_count(mut self, codetag_count: Option<u32>) -> Self {
+        self.header.codetag_count = codetag_count;
+        self
+    }
+
+    /// Set the format of the font.
+    pub fn format(mut self, format: FontFormat) -> Self {
+        self.header.format = format;
+        self
+    }
+
+    /// Set the encoding of the font. `Encoding::Auto` picks ISO-8859-1 for
+    /// FIGlet fonts and UTF-8 for TOIlet fonts, like when reading an ASCII
+    /// font.
+    pub fn encoding(mut self, encoding: Encoding) -> Self {
+        self.header.encoding = encoding;
+        self
+    }
+
+    /// Validate and build the header.
+    pub fn build(self) -> Result<Header> {
+        let mut header = self.header;
+
+        if header.height == 0 {
+            return Err(Error::InvalidHeader("height must be at least 1"));
+        }
+
+        if header.hard_blank_char.is_empty()
+            || header
+                .hard_blank_char
+                .iter()
+                .any(|c| c.is_ascii_whitespace())
+        {
+            return Err(Error::InvalidHeader(
+                "hard blank must be a non-whitespace character",
+            ));
+        }
+
+        if header.encoding == Encoding::Auto {
+            header.encoding = match header.format {
+                FontFormat::Figlet => Encoding::Latin1,
+                FontFormat::Toilet => Encoding::Utf8,
+            };
+        }
+
+        Ok(header)
+    }
+}
+
+impl From<Header> for HeaderBuilder {
+    #[inline]
+    fn from(header: Header) -> Self {
+        HeaderBuilder { header }
+    }
+}
+
+/// The format of a font file.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum FontFormat {
+    /// FIGlet font (.flf), `flf2a` signature, usually ISO-8859-1 encoded.
+    #[default]
+    Figlet,
+    /// TOIlet font (.tlf), `tlf2a` signature, UTF-8 encoded.
+    Toilet,
+}
+
+impl FontFormat {
+    pub(crate) fn magic_number(self) -> &'static [u8] {
+        match self {
+            FontFormat::Figlet => MAGIC_NUMBER,
+            FontFormat::Toilet => TOILET_MAGIC_NUMBER,
+        }
+    }
 }
 
 /// Print direction enum.
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum PrintDirection {
     LeftToRight,
     RightToLeft,
 }
 
 impl FromStr for PrintDirection {
-    type Err = ParseError;
+    type Err = ParseErrorKind;
 
     fn from_str(text: &str) -> std::result::Result<Self, <Self as FromStr>::Err> {
         match text.parse::<u8>() {
-            Ok(n) => match n {
-                0 => Ok(Self::LeftToRight),
-                1 => Ok(Self::RightToLeft),
-                _ => Err(ParseError::InvalidHeader),
-            },
-            Err(_) => Err(ParseError::InvalidHeader),
+            Ok(0) => Ok(Self::LeftToRight),
+            Ok(1) => Ok(Self::RightToLeft),
+            _ => Err(ParseErrorKind::InvalidNumber(6)),
         }
     }
 }
 
-fn read_string_lines<R: Read>(bread: &mut BufReader<R>, num: usize) -> Result<String> {
-    let mut lines = String::new();
+fn read_string_lines<R: Read>(
+    reader: &mut LineReader<R>,
+    num: usize,
+    encoding: Encoding,
+) -> Result<String> {
+    let mut lines = Vec::with_capacity(num);
 
     for _ in 0..num {
-        bread.read_line(&mut lines)?;
+        lines.push(reader.read_line()?);
     }
 
-    if lines.ends_with("\r\n") {
-        lines.truncate(lines.len() - 2);
-    } else if lines.ends_with("\n") {
-        lines.truncate(lines.len() - 1);
-    } else {
-        return Err(ParseError::NotEnoughData.into());
-    }
+    Ok(encoding.decode_lossy(&lines.join(&b'\n')))
+}
 
-    Ok(lines)
+/// A header field and its byte column.
+type Field<'a> = (usize, &'a [u8]);
+
+/// Parse the numeric header field `i` of the header at `position`.
+fn parse_field<T: FromStr>(
+    fields: &[Field<'_>],
+    i: usize,
+    (line, offset): (usize, u64),
+) -> std::result::Result<T, ParseError> {
+    let (column, field) = fields[i];
+    from_utf8(field)
+        .ok()
+        .and_then(|field| field.parse().ok())
+        .ok_or_else(|| {
+            ParseError::new(ParseErrorKind::InvalidNumber(i), line, offset).at_column(column)
+        })
 }
 
-macro_rules! parse {
-    ($arg:expr) => {
-        parse!($arg, _)
-    };
+/// Parse the optional numeric header field `i`. When not strict, invalid
+/// ones are ignored.
+fn parse_optional_field<T: FromStr, R: Read>(
+    reader: &mut LineReader<R>,
+    fields: &[Field<'_>],
+    i: usize,
+    position: (usize, u64),
+) -> Result<Option<T>> {
+    if i >= fields.len() {
+        return Ok(None);
+    }
 
-    ($arg:expr, $t:ty) => {
-        match from_utf8($arg)
-            .map_err(|_| ParseError::InvalidHeader)?
-            .parse::<$t>()
-        {
-            Ok(res) => Some(res),
-            Err(_) => {
-                return Err(ParseError::InvalidHeader.into());
-            }
-        }
-    };
+    match parse_field(fields, i, position) {
+        Ok(value) => Ok(Some(value)),
+        Err(error) => reader.warn(error).map(|_| None),
+    }
 }
 
-fn parse_header<R: Read>(bread: &mut BufReader<R>) -> Result<Header> {
-    let header = read_line(bread)?;
-    let header: Vec<u8> = if header.starts_with(MAGIC_NUMBER) {
-        header.into_iter().skip(MAGIC_NUMBER.len()).collect()
+fn parse_header<R: Read>(reader: &mut LineReader<R>, encoding: Encoding) -> Result<Header> {
+    let position = reader.position();
+    let error = |kind| ParseError::new(kind, position.0, position.1);
+
+    let header = reader.read_line()?;
+    let format = if header.starts_with(MAGIC_NUMBER) {
+        FontFormat::Figlet
+    } else if header.starts_with(TOILET_MAGIC_NUMBER) {
+        FontFormat::Toilet
     } else {
-        return Err(ParseError::InvalidHeader.into());
+        return Err(error(ParseErrorKind::InvalidSignature).into());
     };
 
-    let arguments: Vec<&[u8]> = header
+    let signature_len = format.magic_number().len();
+    let mut arguments: Vec<Field<'_>> = header[signature_len..]
         .split(|c| c == &b' ')
+        .scan(signature_len + 1, |column, x| {
+            let start = *column;
+            *column += x.len() + 1;
+            Some((start, x))
+        })
         .enumerate()
-        .filter(|(i, x)| *i == 0 || !x.is_empty())
+        .filter(|(i, (_, x))| *i == 0 || !x.is_empty())
         .map(|(_, x)| x)
         .collect();
 
-    if arguments.len() < 6 || arguments.len() > 9 {
-        return Err(ParseError::InvalidHeader.into());
+    if arguments.len() < 6 {
+        return Err(error(ParseErrorKind::HeaderFieldCount(arguments.len())).into());
+    } else if arguments.len() > 9 {
+        let (column, _) = arguments[9];
+        reader.warn(error(ParseErrorKind::HeaderFieldCount(arguments.len())).at_column(column))?;
+        arguments.truncate(9);
     }
 
-    if arguments[0].is_empty() {
-        return Err(ParseError::InvalidHeader.into());
+    let (column, hard_blank_char) = arguments[0];
+    if hard_blank_char.is_empty() {
+        return Err(error(ParseErrorKind::MissingHardBlank)
+            .at_column(column)
+            .into());
     }
 
-    let mut hard_blank_char: Vec<u8> = Vec::with_capacity(arguments[0].len());
-    hard_blank_char.extend_from_slice(arguments[0]);
-    let height: usize = parse!(arguments[1]).ok_or(ParseError::InvalidHeader)?;
-    let baseline: usize = parse!(arguments[2]).ok_or(ParseError::InvalidHeader)?;
-    let max_length: usize = parse!(arguments[3]).ok_or(ParseError::InvalidHeader)?;
-    let old_layout: i32 = parse!(arguments[4]).ok_or(ParseError::InvalidHeader)?;
-
-    let print_direction: PrintDirection = if arguments.len() > 6 {
-        parse!(arguments[6]).ok_or(ParseError::InvalidHeader)?
-    } else {
-        PrintDirection::LeftToRight
-    };
-
-    let layout: Layout = if arguments.len() > 7 {
-        parse!(arguments[7]).ok_or(ParseError::InvalidHeader)?
-    } else {
-        full_layout_from_old_layout(old_layout)
-    };
+    let hard_blank_char = hard_blank_char.to_vec();
+    let height: usize = parse_field(&arguments, 1, position)?;
+    if height == 0 {
+        let (column, _) = arguments[1];
+        return Err(error(ParseErrorKind::InvalidNumber(1))
+            .at_column(column)
+            .into());
+    }
+    let baseline: usize = parse_field(&arguments, 2, position)?;
+    let max_length: usize = parse_field(&arguments, 3, position)?;
+    let old_layout: i32 = parse_field(&arguments, 4, position)?;
+    let comment_lines: usize = parse_field(&arguments, 5, position)?;
+
+    let print_direction: PrintDirection = parse_optional_field(reader, &arguments, 6, position)?
+        .unwrap_or(PrintDirection::LeftToRight);
+    let full_layout: Option<u32> = parse_optional_field(reader, &arguments, 7, position)?;
+    let codetag_count: Option<u32> = parse_optional_field(reader, &arguments, 8, position)?;
+
+    if baseline == 0 || baseline > height {
+        let (column, _) = arguments[2];
+        let kind = ParseErrorKind::InvalidBaseline { baseline, height };
+        reader.lint(error(kind).at_column(column));
+    }
 
-    let codetag_count: Option<u32> = if arguments.len() > 8 {
-        Some(parse!(arguments[8]).ok_or(ParseError::InvalidHeader)?)
-    } else {
-        None
-    };
+    if let Some(full_layout) = full_layout {
+        if Layout::from_bits_retain(full_layout).to_old_layout() != old_layout {
+            let (column, _) = arguments[7];
+            let kind = ParseErrorKind::LayoutMismatch {
+                old_layout,
+                full_layout,
+            };
+            reader.lint(error(kind).at_column(column));
+        }
+    }
 
-    let comment: String = {
-        let comment_lines: usize = parse!(arguments[5]).ok_or(ParseError::InvalidHeader)?;
-        read_string_lines(bread, comment_lines)?
-    };
+    let comment = read_string_lines(reader, comment_lines, encoding)?;
 
     Ok(Header {
         hard_blank_char: hard_blank_char.into_boxed_slice(),
@@ -234,17 +515,162 @@ fn parse_header<R: Read>(bread: &mut BufReader<R>) -> Result<Header> {
         max_length,
         comment: comment.into_boxed_str(),
         print_direction,
-        layout,
+        old_layout,
+        full_layout,
         codetag_count,
+        format,
+        encoding,
     })
 }
 
-fn full_layout_from_old_layout(old_layout: i32) -> Layout {
-    use std::cmp::Ordering::*;
+fn write_header<W: Write>(
+    header: &Header,
+    writer: &mut W,
+    max_length: usize,
+    codetag_count: Option<u32>,
+) -> Result<()> {
+    let comment = header.encoding.encode(&header.comment)?;
+    let comment_lines = if comment.is_empty() {
+        0
+    } else {
+        header.comment.split('\n').count()
+    };
 
-    Layout::from_bits_truncate(match old_layout.cmp(&0) {
-        Equal => 64,
-        Less => 0,
-        Greater => (old_layout as u32 & 31) | 128,
-    })
+    writer.write_all(header.format.magic_number())?;
+    writer.write_all(&header.hard_blank_char)?;
+    write!(
+        writer,
+        " {} {} {} {} {} {}",
+        header.height,
+        header.baseline,
+        max_length,
+        header.old_layout,
+        comment_lines,
+        match header.print_direction {
+            PrintDirection::LeftToRight => 0,
+            PrintDirection::RightToLeft => 1,
+        },
+    )?;
+    if header.full_layout.is_some() || codetag_count.is_some() {
+        write!(writer, " {}", header.layout().bits())?;
+    }
+    if let Some(codetag_count) = codetag_count {
+        write!(writer, " {}", codetag_count)?;
+    }
+    writeln!(writer)?;
+
+    if comment_lines != 0 {
+        writer.write_all(&comment)?;
+        writeln!(writer)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{HeaderBuilder, Layout};
+    use crate::{error::ParseErrorKind, Encoding, FontFormat};
+
+    #[test]
+    fn layout_bits() {
+        assert_eq!("16384".parse::<Layout>().unwrap(), Layout::VERTICAL_SMUSH);
+        assert_eq!(
+            "16392".parse::<Layout>().unwrap(),
+            Layout::VERTICAL_SMUSH | Layout::HORIZONTAL_PAIR
+        );
+        assert_eq!("32768".parse::<Layout>().unwrap().bits(), 32768);
+        assert!("-1".parse::<Layout>().is_err());
+    }
+
+    #[test]
+    fn old_layout() {
+        for old_layout in -1..32 {
+            assert_eq!(
+                Layout::from_old_layout(old_layout).to_old_layout(),
+                old_layout
+            );
+        }
+        for rules in 1..64 {
+            let layout = Layout::from_bits_retain(rules) | Layout::HORIZONTAL_SMUSH;
+            assert_eq!(layout.to_old_layout(), rules as i32);
+        }
+
+        assert_eq!(Layout::from_old_layout(-3), Layout::empty());
+        assert_eq!(Layout::from_old_layout(0), Layout::HORIZONTAL_KERNING);
+        assert_eq!(
+            Layout::from_old_layout(33),
+            Layout::HORIZONTAL_SMUSH | Layout::HORIZONTAL_EQUAL
+        );
+        assert_eq!(
+            (Layout::HORIZONTAL_SMUSH | Layout::HORIZONTAL_HARDBLANK).to_old_layout(),
+            32
+        );
+        assert_eq!(Layout::HORIZONTAL_SMUSH.to_old_layout(), 0);
+        assert_eq!(
+            (Layout::VERTICAL_SMUSH | Layout::HORIZONTAL_KERNING).to_old_layout(),
+            0
+        );
+    }
+
+    #[test]
+    fn raw_layouts() {
+        let font = |header: &str| {
+            crate::FIGfont::read_from(crate::utils::test_font(header).as_bytes()).unwrap()
+        };
+
+        let unknown = font("flf2a$ 1 1 3 15 0 0 82048");
+        assert_eq!(unknown.header().old_layout(), 15);
+        assert_eq!(unknown.header().full_layout(), Some(82048));
+        assert_eq!(
+            unknown.header().layout(),
+            Layout::from_bits_retain(65536) | Layout::HORIZONTAL_SMUSH | Layout::VERTICAL_SMUSH
+        );
+
+        let old = font("flf2a$ 1 1 3 5 0");
+        assert_eq!(old.header().full_layout(), None);
+        assert_eq!(old.header().layout(), Layout::from_old_layout(5));
+
+        let hardblank = font("flf2a$ 1 1 3 47 0 0 175");
+        assert!(hardblank
+            .header()
+            .layout()
+            .contains(Layout::HORIZONTAL_HARDBLANK));
+        let mismatch = crate::utils::test_font("flf2a$ 1 1 3 33 0 0 129");
+        let report = crate::Report::read_from(mismatch.as_bytes()).unwrap();
+        assert_eq!(
+            report.problems()[0].kind(),
+            ParseErrorKind::LayoutMismatch {
+                old_layout: 33,
+                full_layout: 129
+            }
+        );
+
+        for font in [unknown, old, hardblank] {
+            let mut written = Vec::new();
+            font.write_to(&mut written).unwrap();
+            assert_eq!(crate::FIGfont::read_from(&written[..]).unwrap(), font);
+        }
+    }
+
+    #[test]
+    fn builder() {
+        let header = HeaderBuilder::new(2).build().unwrap();
+        assert_eq!(header.hard_blank_char(), b"$");
+        assert_eq!(header.encoding(), Encoding::Latin1);
+
+        assert_eq!(HeaderBuilder::from(header.clone()).build().unwrap(), header);
+
+        let header = HeaderBuilder::new(2)
+            .format(FontFormat::Toilet)
+            .hard_blank_char("\u{a0}")
+            .build()
+            .unwrap();
+        assert_eq!(header.hard_blank_char(), "\u{a0}".as_bytes());
+        assert_eq!(header.encoding(), Encoding::Utf8);
+
+        assert!(HeaderBuilder::new(0).build().is_err());
+        assert!(HeaderBuilder::new(1).hard_blank_char("").build().is_err());
+        assert!(HeaderBuilder::new(1).hard_blank_char(" ").build().is_err());
+    }
 }
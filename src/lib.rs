@@ -1,46 +1,172 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::fmt::{self, Debug, Formatter};
 use std::fs::File;
-use std::io::{BufRead, BufReader, Read};
+use std::io::{BufReader, BufWriter, Cursor, Read, Seek, SeekFrom, Write};
 use std::path::Path;
+use std::sync::Arc;
 
-use error::ParseError;
+use error::{Error, ParseError, ParseErrorKind};
 
+pub mod builder;
 pub mod character;
+pub mod charset;
+pub mod codetag;
+pub mod control;
 pub mod error;
+pub mod figure;
 pub mod header;
+pub mod library;
+pub mod render;
 pub mod result;
+pub mod smushing;
 pub mod subcharacter;
 mod utils;
+pub mod validate;
 
+use crate::character::Glyph;
 use crate::result::Result;
+use crate::utils::LineReader;
 
 const DEUTSCH_CODE_POINTS: [i32; 7] = [196, 214, 220, 228, 246, 252, 223];
 
 const STANDARD_FONT: &[u8] = include_bytes!("../fonts/plain/standard.flf");
 
+const ZIP_MAGIC_NUMBER: &[u8] = b"PK";
+#[cfg(feature = "gzip")]
+const GZIP_MAGIC_NUMBER: &[u8] = b"\x1f\x8b";
+#[cfg(feature = "zstd")]
+const ZSTD_MAGIC_NUMBER: &[u8] = b"\x28\xb5\x2f\xfd";
+
+const FONT_EXTENSIONS: [&str; 2] = [".flf", ".tlf"];
+const COMPRESSION_EXTENSIONS: [(bool, &str); 2] = [
+    (cfg!(feature = "gzip"), ".gz"),
+    (cfg!(feature = "zstd"), ".zst"),
+];
+
 pub use crate::{
+    builder::FIGfontBuilder,
     character::FIGcharacter,
-    header::{Header, Layout, PrintDirection},
+    charset::Encoding,
+    codetag::Codetag,
+    control::ControlFile,
+    figure::FIGure,
+    header::{FontFormat, Header, HeaderBuilder, Layout, PrintDirection},
+    library::FontLibrary,
+    render::{Justification, LayoutMode, MissingCharacter, RenderOptions, Renderer},
     subcharacter::SubCharacter,
+    validate::Report,
 };
 
+/// Options for reading and parsing a font.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseOptions {
+    /// The encoding of the font file.
+    pub encoding: Encoding,
+    /// Fail on anything breaking the spec. Otherwise recover where possible,
+    /// reporting what was wrong as warnings. Defaults to true.
+    pub strict: bool,
+    /// Only index the FIGcharacters while reading the font, and parse each
+    /// one the first time it's used. Faster for big Unicode fonts when only
+    /// a few characters are rendered. The whole font is still checked, and
+    /// kept in memory. Defaults to false.
+    pub lazy: bool,
+}
+
+impl Default for ParseOptions {
+    fn default() -> Self {
+        ParseOptions {
+            encoding: Encoding::default(),
+            strict: true,
+            lazy: false,
+        }
+    }
+}
+
 /// FIGfont reader and parser.
-#[derive(Debug, Clone)]
+#[derive(Clone)]
 pub struct FIGfont {
     header: Header,
-    characters: HashMap<i32, FIGcharacter>,
+    characters: HashMap<i32, Glyph>,
+    /// The codes of the FIGcharacters read from codetagged entries.
+    codetagged: HashSet<i32>,
+    /// The font file, when lazily parsed.
+    source: Arc<[u8]>,
 }
 
 impl FIGfont {
-    /// Read and parse a FIGfont from a path. It can be zipped if you have zip
-    /// feature enabled.
+    /// Read and parse a FIGfont or a TOIlet font from a path. It can be zipped,
+    /// gzipped (`.flf.gz`) or zstd compressed (`.flf.zst`) if you have the
+    /// zip, gzip or zstd feature enabled.
     pub fn load_from<P: AsRef<Path>>(path: P) -> Result<FIGfont> {
-        load_from(path)
+        FIGfont::load_from_with(path, &ParseOptions::default())
+    }
+
+    /// Read and parse a FIGfont or a TOIlet font from a path with custom
+    /// options.
+    pub fn load_from_with<P: AsRef<Path>>(path: P, options: &ParseOptions) -> Result<FIGfont> {
+        FIGfont::load_from_with_warnings(path, options).map(|(font, _)| font)
+    }
+
+    /// Read and parse a FIGfont or a TOIlet font from a path with custom
+    /// options, also returning the warnings of a non-strict parse.
+    pub fn load_from_with_warnings<P: AsRef<Path>>(
+        path: P,
+        options: &ParseOptions,
+    ) -> Result<(FIGfont, Vec<ParseError>)> {
+        load_from(path, &|f| parse(f, options, false))
+    }
+
+    /// List the fonts (.flf and .tlf entries) of a zip archive.
+    #[cfg(feature = "zip")]
+    pub fn zip_entries<P: AsRef<Path>>(path: P) -> Result<Vec<String>> {
+        zip_entries(path)
+    }
+
+    /// Read and parse the font `entry` of a zip archive.
+    #[cfg(feature = "zip")]
+    pub fn load_from_zip_entry<P: AsRef<Path>>(path: P, entry: &str) -> Result<FIGfont> {
+        read_zip_entry(path, entry, |f| parse(f, &ParseOptions::default(), false))
+            .map(|(font, _)| font)
     }
 
     /// Read and parse a FIGfont from a impl Read.
     pub fn read_from<R: Read>(reader: R) -> Result<FIGfont> {
-        parse(reader)
+        FIGfont::read_from_with(reader, &ParseOptions::default())
+    }
+
+    /// Read and parse a FIGfont or a TOIlet font from memory. It can be
+    /// zipped, gzipped or zstd compressed if you have the matching feature
+    /// enabled.
+    pub fn from_bytes(bytes: &[u8]) -> Result<FIGfont> {
+        FIGfont::read_from_seekable(Cursor::new(bytes))
+    }
+
+    /// Read and parse a FIGfont or a TOIlet font from a impl Read + Seek,
+    /// starting at its current position. It can be zipped, gzipped or zstd
+    /// compressed if you have the matching feature enabled.
+    pub fn read_from_seekable<R: Read + Seek>(reader: R) -> Result<FIGfont> {
+        read_sniffed(reader, &|f| parse(f, &ParseOptions::default(), false)).map(|(font, _)| font)
+    }
+
+    /// Read and parse a FIGfont from a impl Read with custom options.
+    pub fn read_from_with<R: Read>(reader: R, options: &ParseOptions) -> Result<FIGfont> {
+        parse(reader, options, false).map(|(font, _)| font)
+    }
+
+    /// Read and parse a FIGfont from a impl Read with custom options, also
+    /// returning the warnings of a non-strict parse.
+    pub fn read_from_with_warnings<R: Read>(
+        reader: R,
+        options: &ParseOptions,
+    ) -> Result<(FIGfont, Vec<ParseError>)> {
+        parse(reader, options, false)
+    }
+
+    /// Serialize the FIGfont to a impl Write, in the font's format and
+    /// encoding. The max length written is raised to the longest line if the
+    /// header's is too short.
+    pub fn write_to<W: Write>(&self, writer: W) -> Result<()> {
+        write(self, writer)
     }
 
     /// Get the standard FIGfont. (hardcoded)
@@ -53,108 +179,833 @@ impl FIGfont {
         &self.header
     }
 
+    /// Get the encoding the font was decoded with. Never `Encoding::Auto`.
+    pub fn encoding(&self) -> Encoding {
+        self.header.encoding()
+    }
+
     /// Get the FIGcharacter for the `code` character.
+    ///
+    /// If the font doesn't have it, the font's missing character (code 0) is
+    /// returned, then `~`, and finally an empty FIGcharacter.
     pub fn get(&self, code: i32) -> &FIGcharacter {
+        self.try_get(code)
+            .or_else(|| self.try_get(0))
+            .or_else(|| self.try_get(126))
+            .unwrap_or_else(|| FIGcharacter::empty())
+    }
+
+    /// Get the FIGcharacter for the `code` character, if the font has it.
+    pub fn try_get(&self, code: i32) -> Option<&FIGcharacter> {
         self.characters
             .get(&code)
-            .unwrap_or_else(|| self.characters.get(&126i32).unwrap())
+            .map(|glyph| glyph.get(&self.source, &self.header, code))
+    }
+
+    /// Get the FIGcharacter for `c`, with the same fallbacks as `get`.
+    pub fn get_char(&self, c: char) -> &FIGcharacter {
+        self.get(Codetag::from(c).code())
+    }
+
+    /// Get the FIGcharacter for `c`, if the font has it.
+    pub fn try_get_char(&self, c: char) -> Option<&FIGcharacter> {
+        self.try_get(Codetag::from(c).code())
+    }
+
+    /// Check if the font has a FIGcharacter for `c`.
+    pub fn contains(&self, c: char) -> bool {
+        self.try_get_char(c).is_some()
+    }
+
+    /// Iterate over the codes of the font's FIGcharacters, in no particular
+    /// order.
+    pub fn codetags(&self) -> impl Iterator<Item = Codetag> + '_ {
+        self.characters.keys().map(|code| Codetag::from(*code))
+    }
+
+    /// Render `text` with the font's own layout.
+    pub fn render(&self, text: &str) -> Result<FIGure> {
+        Renderer::new(self).render(text)
+    }
+
+    /// Render `text` with custom options.
+    pub fn render_with(&self, text: &str, options: &RenderOptions) -> Result<FIGure> {
+        Renderer::with_options(self, options.clone()).render(text)
+    }
+
+    pub(crate) fn new(
+        header: Header,
+        characters: HashMap<i32, FIGcharacter>,
+        codetagged: HashSet<i32>,
+    ) -> Self {
+        FIGfont {
+            header,
+            characters: characters
+                .into_iter()
+                .map(|(code, character)| (code, Glyph::from(character)))
+                .collect(),
+            codetagged,
+            source: Arc::from(&[][..]),
+        }
+    }
+
+    /// Split the font into its header, FIGcharacters and codetagged codes,
+    /// parsing the FIGcharacters not used yet.
+    pub(crate) fn into_parts(self) -> (Header, HashMap<i32, FIGcharacter>, HashSet<i32>) {
+        let FIGfont {
+            header,
+            characters,
+            codetagged,
+            source,
+        } = self;
+        let characters = characters
+            .into_iter()
+            .map(|(code, glyph)| (code, glyph.into_character(&source, &header, code)))
+            .collect();
+
+        (header, characters, codetagged)
     }
 }
 
-fn parse<R: Read>(reader: R) -> Result<FIGfont> {
-    let mut bread /* mlmlmlml */ = BufReader::new(reader);
+impl Debug for FIGfont {
+    fn fmt(&self, fmt: &mut Formatter<'_>) -> fmt::Result {
+        fmt.debug_struct("FIGfont")
+            .field("header", &self.header)
+            .field("characters", &self.characters)
+            .field("codetagged", &self.codetagged)
+            .finish_non_exhaustive()
+    }
+}
 
-    let header = Header::parse(&mut bread)?;
+impl PartialEq for FIGfont {
+    fn eq(&self, other: &Self) -> bool {
+        self.header == other.header
+            && self.codetagged == other.codetagged
+            && self.characters.len() == other.characters.len()
+            && self
+                .characters
+                .keys()
+                .all(|code| self.try_get(*code) == other.try_get(*code))
+    }
+}
 
-    let mut characters = HashMap::new();
+impl Eq for FIGfont {}
+
+/// Parse a font, returning the warnings of a non-strict parse. Linting also
+/// reports the problems which don't stop parsing.
+pub(crate) fn parse<R: Read>(
+    reader: R,
+    options: &ParseOptions,
+    lint: bool,
+) -> Result<(FIGfont, Vec<ParseError>)> {
+    if options.encoding != Encoding::Auto && !options.lazy {
+        let reader = LineReader::new(reader, options.strict);
+        return parse_encoded(reader, options.encoding, false, lint);
+    }
 
-    for codepoint in 32..127 {
-        characters.insert(codepoint, FIGcharacter::parse(&mut bread, &header)?);
+    let mut bytes = Vec::new();
+    BufReader::new(reader).read_to_end(&mut bytes)?;
+    let encoding = match options.encoding {
+        Encoding::Auto => Encoding::detect(&bytes),
+        encoding => encoding,
+    };
+    let reader = LineReader::new(&bytes[..], options.strict);
+    let (mut font, warnings) = parse_encoded(reader, encoding, options.lazy, lint)?;
+    if options.lazy {
+        font.source = Arc::from(bytes);
     }
 
-    for codepoint in DEUTSCH_CODE_POINTS.iter() {
-        let codepoint = *codepoint;
+    Ok((font, warnings))
+}
+
+fn parse_encoded<R: Read>(
+    reader: LineReader<R>,
+    encoding: Encoding,
+    lazy: bool,
+    lint: bool,
+) -> Result<(FIGfont, Vec<ParseError>)> {
+    let mut reader = if lint { reader.linting() } else { reader };
 
-        characters.insert(codepoint, FIGcharacter::parse(&mut bread, &header)?);
+    let header = Header::parse(&mut reader, encoding)?;
+
+    let mut characters = HashMap::new();
+    let mut codetagged = HashSet::new();
+    parse_characters(&mut reader, &header, &mut characters, &mut codetagged, lazy)?;
+
+    let font = FIGfont {
+        header,
+        characters,
+        codetagged,
+        source: Arc::from(&[][..]),
+    };
+
+    Ok((font, reader.into_warnings()))
+}
+
+/// Parse the FIGcharacters, or only index them if lazy. When not strict, a
+/// truncated font keeps the ones before its end.
+fn parse_characters<R: Read>(
+    reader: &mut LineReader<R>,
+    header: &Header,
+    characters: &mut HashMap<i32, Glyph>,
+    codetagged: &mut HashSet<i32>,
+    lazy: bool,
+) -> Result<()> {
+    let mut required = (32..127).chain(DEUTSCH_CODE_POINTS.iter().copied());
+    while let Some(codepoint) = required.next() {
+        if reader.is_empty()? {
+            let missing = |code| {
+                reader
+                    .error(ParseErrorKind::MissingCharacter)
+                    .in_character(Some(Codetag::from(code)))
+            };
+            let rest: Vec<_> = required.map(missing).collect();
+            reader.warn(missing(codepoint))?;
+            rest.into_iter().for_each(|error| reader.lint(error));
+            return Ok(());
+        }
+
+        let character = Glyph::parse(reader, header, codepoint, lazy);
+        let Some(character) = until_eof(reader, character)? else {
+            return Ok(());
+        };
+        characters.insert(codepoint, character);
     }
 
     let mut cnt = 0;
-    while !bread.fill_buf()?.is_empty() {
-        let (codepoint, character) = FIGcharacter::parse_with_codetag(&mut bread, &header)?;
-        characters.insert(codepoint, character);
+    while !reader.is_empty()? {
+        let duplicate = reader.error(ParseErrorKind::DuplicateCodetag);
+        let character = Glyph::parse_with_codetag(reader, header, lazy);
+        let Some(character) = until_eof(reader, character)? else {
+            return Ok(());
+        };
+        if let Some((codepoint, character)) = character {
+            if !codetagged.insert(codepoint) {
+                reader.lint(duplicate.in_character(Some(Codetag::from(codepoint))));
+            }
+            characters.insert(codepoint, character);
+        }
         cnt += 1;
     }
 
-    if let Some(expected_cnt) = header.codetag_count() {
-        if expected_cnt != cnt {
-            return Err(ParseError::InvalidFont.into());
+    if let Some(expected) = header.codetag_count() {
+        if expected != cnt {
+            let kind = ParseErrorKind::CodetagCount {
+                expected,
+                found: cnt,
+            };
+            reader.warn(reader.error(kind))?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Turn the end of a truncated font into a warning, when not strict.
+fn until_eof<R: Read, T>(reader: &mut LineReader<R>, result: Result<T>) -> Result<Option<T>> {
+    match result {
+        Err(Error::Parse(error)) if error.kind() == ParseErrorKind::UnexpectedEof => {
+            reader.warn(error).map(|_| None)
+        }
+        result => result.map(Some),
+    }
+}
+
+fn write<W: Write>(font: &FIGfont, writer: W) -> Result<()> {
+    let mut writer = BufWriter::new(writer);
+
+    // Required characters are written again as codetagged ones only if they
+    // were read from one.
+    let mut codetags: Vec<i32> = font
+        .characters
+        .keys()
+        .copied()
+        .filter(|code| {
+            font.codetagged.contains(code)
+                || !((32..127).contains(code) || DEUTSCH_CODE_POINTS.contains(code))
+        })
+        .collect();
+    codetags.sort_unstable();
+
+    let codetag_count = font.header.codetag_count().map(|_| codetags.len() as u32);
+
+    let mut characters: Vec<(Option<i32>, &FIGcharacter)> = (32..127)
+        .chain(DEUTSCH_CODE_POINTS.iter().copied())
+        .map(|code| {
+            (
+                None,
+                font.try_get(code).unwrap_or_else(|| FIGcharacter::empty()),
+            )
+        })
+        .collect();
+    characters.extend(
+        codetags
+            .into_iter()
+            .map(|code| (Some(code), font.get(code))),
+    );
+    let rows = characters
+        .iter()
+        .map(|(_, character)| character.encode(&font.header))
+        .collect::<Result<Vec<_>>>()?;
+
+    // The max length is what the written lines need, so that fonts built
+    // or edited in memory stay valid.
+    let max_length = rows
+        .iter()
+        .flatten()
+        .map(|row| character::line_length(row, font.header.encoding()))
+        .fold(font.header.max_length(), usize::max);
+    font.header.write(&mut writer, max_length, codetag_count)?;
+
+    for ((code, character), rows) in characters.iter().zip(rows) {
+        if let Some(code) = code {
+            character.write_codetag(&mut writer, &font.header, *code)?;
+        }
+        for row in rows {
+            writer.write_all(&row)?;
+            writeln!(writer)?;
+        }
+    }
+
+    writer.flush()?;
+
+    Ok(())
+}
+
+/// Parse `reader` with `parse`, after decompressing or unzipping it.
+pub(crate) fn read_sniffed<R, T, F>(mut reader: R, parse: &F) -> Result<T>
+where
+    R: Read + Seek,
+    F: Fn(&mut dyn Read) -> Result<T>,
+{
+    let start = reader.stream_position()?;
+    let mut magic = Vec::with_capacity(4);
+    reader.by_ref().take(4).read_to_end(&mut magic)?;
+    reader.seek(SeekFrom::Start(start))?;
+
+    #[cfg(feature = "gzip")]
+    {
+        if magic.starts_with(GZIP_MAGIC_NUMBER) {
+            let decoder = flate2::read::GzDecoder::new(reader);
+            let bytes = decompress(decoder).map_err(error::Io::Gzip)?;
+            return read_sniffed(Cursor::new(bytes), parse);
+        }
+    }
+
+    #[cfg(feature = "zstd")]
+    {
+        if magic.starts_with(ZSTD_MAGIC_NUMBER) {
+            let bytes = zstd::stream::read::Decoder::new(reader)
+                .and_then(decompress)
+                .map_err(error::Io::Zstd)?;
+            return read_sniffed(Cursor::new(bytes), parse);
+        }
+    }
+
+    if !magic.starts_with(ZIP_MAGIC_NUMBER) {
+        return parse(&mut reader);
+    }
+
+    #[cfg(feature = "zip")]
+    {
+        read_zip(reader, parse)
+    }
+
+    #[cfg(not(feature = "zip"))]
+    {
+        Err(Error::InvalidFont("zip archives need the zip feature"))
+    }
+}
+
+#[cfg(any(feature = "gzip", feature = "zstd"))]
+fn decompress<R: Read>(mut decoder: R) -> std::io::Result<Vec<u8>> {
+    let mut bytes = Vec::new();
+    decoder.read_to_end(&mut bytes)?;
+    Ok(bytes)
+}
+
+/// Get the name of a font file: its file name without the `.flf` or `.tlf`
+/// extension and the compression one, if any.
+pub(crate) fn font_name(path: &Path) -> Option<&str> {
+    let mut name = path.file_name()?.to_str()?;
+    for (_, ext) in COMPRESSION_EXTENSIONS
+        .iter()
+        .filter(|(enabled, _)| *enabled)
+    {
+        if let Some(stripped) = name.strip_suffix(ext) {
+            name = stripped;
+            break;
         }
     }
 
-    Ok(FIGfont { header, characters })
+    FONT_EXTENSIONS
+        .iter()
+        .find_map(|ext| name.strip_suffix(ext))
+        .filter(|name| !name.is_empty())
 }
 
+/// Get the file names a font can have, adding the missing extensions to
+/// `name`.
+pub(crate) fn font_file_names(name: &str) -> Vec<String> {
+    let extensions: &[&str] = if FONT_EXTENSIONS.iter().any(|ext| name.ends_with(ext)) {
+        &[""]
+    } else {
+        &FONT_EXTENSIONS
+    };
+
+    extensions
+        .iter()
+        .flat_map(|ext| {
+            std::iter::once("")
+                .chain(
+                    COMPRESSION_EXTENSIONS
+                        .iter()
+                        .filter(|(enabled, _)| *enabled)
+                        .map(|(_, compression)| *compression),
+                )
+                .map(move |compression| format!("{}{}{}", name, ext, compression))
+        })
+        .collect()
+}
+
+/// Parse the first font entry of a zip archive, or its only entry.
 #[cfg(feature = "zip")]
-fn load_from_zip<P: AsRef<Path>>(path: P) -> Result<FIGfont> {
-    use crate::error::Error;
-    use zip::ZipArchive;
+fn read_zip<R, T, F>(reader: R, parse: F) -> Result<T>
+where
+    R: Read + Seek,
+    F: FnOnce(&mut dyn Read) -> Result<T>,
+{
+    let mut zip = zip::ZipArchive::new(reader)?;
+
+    let names: Vec<String> = zip.file_names().map(String::from).collect();
+    let name = names
+        .iter()
+        .find(|name| is_font_entry(name))
+        .or_else(|| names.first().filter(|_| names.len() == 1))
+        .ok_or(Error::InvalidFont("no font in zip archive"))?;
+    let mut f = zip.by_name(name)?;
+
+    parse(&mut f)
+}
 
-    let mut zip = ZipArchive::new(File::open(path.as_ref())?)?;
+#[cfg(feature = "zip")]
+fn is_font_entry(name: &str) -> bool {
+    font_name(Path::new(name)).is_some()
+}
 
+#[cfg(feature = "zip")]
+fn load_from_zip<P, T, F>(path: P, parse: F) -> Result<T>
+where
+    P: AsRef<Path>,
+    F: FnOnce(&mut dyn Read) -> Result<T>,
+{
     let file_name = path
         .as_ref()
         .file_name()
-        .ok_or::<Error>(ParseError::InvalidFont.into())?
-        .to_str()
-        .ok_or::<Error>(ParseError::InvalidFont.into())?;
+        .and_then(|file_name| file_name.to_str())
+        .ok_or(Error::InvalidExtension)?;
 
-    let f = zip.by_name(file_name)?;
+    read_zip_entry(path.as_ref(), file_name, parse)
+}
 
-    parse(f)
+#[cfg(feature = "zip")]
+fn read_zip_entry<P, T, F>(path: P, entry: &str, parse: F) -> Result<T>
+where
+    P: AsRef<Path>,
+    F: FnOnce(&mut dyn Read) -> Result<T>,
+{
+    let mut zip = zip::ZipArchive::new(File::open(path.as_ref())?)?;
+    let mut f = zip.by_name(entry)?;
+
+    parse(&mut f)
 }
 
 #[cfg(feature = "zip")]
-fn is_plain<P: AsRef<Path>>(path: P) -> Result<bool> {
-    let mut f = File::open(path)?;
-    let mut number: [u8; 5] = [0; 5];
-    f.read_exact(&mut number)?;
-    Ok(&number == b"flf2a")
+fn zip_entries<P: AsRef<Path>>(path: P) -> Result<Vec<String>> {
+    let zip = zip::ZipArchive::new(File::open(path.as_ref())?)?;
+
+    Ok(zip
+        .file_names()
+        .filter(|name| is_font_entry(name))
+        .map(String::from)
+        .collect())
 }
 
-fn load_from<P: AsRef<Path>>(path: P) -> Result<FIGfont> {
+/// Parse the font file at `path` with `parse`, after decompressing or
+/// unzipping it.
+pub(crate) fn load_from<P, T, F>(path: P, parse: &F) -> Result<T>
+where
+    P: AsRef<Path>,
+    F: Fn(&mut dyn Read) -> Result<T>,
+{
     let path = path.as_ref();
-    match path.extension() {
-        Some(ext) => {
-            if ext != "flf" {
-                return Err(ParseError::InvalidExtension.into());
-            }
-        }
-        None => {
-            return Err(ParseError::InvalidExtension.into());
-        }
+    if font_name(path).is_none() {
+        return Err(Error::InvalidExtension);
     }
 
+    load_sniffed(path, parse)
+}
+
+/// Parse the file at `path` with `parse`, after decompressing it or reading
+/// the zip entry named like the file.
+pub(crate) fn load_sniffed<T, F>(path: &Path, parse: &F) -> Result<T>
+where
+    F: Fn(&mut dyn Read) -> Result<T>,
+{
     #[cfg(feature = "zip")]
     {
-        if is_plain(path)? {
-            parse(File::open(path)?)
-        } else {
-            load_from_zip(path)
+        let mut magic = Vec::with_capacity(ZIP_MAGIC_NUMBER.len());
+        File::open(path)?
+            .take(ZIP_MAGIC_NUMBER.len() as u64)
+            .read_to_end(&mut magic)?;
+        if magic == ZIP_MAGIC_NUMBER {
+            return load_from_zip(path, parse);
         }
     }
 
-    #[cfg(not(feature = "zip"))]
-    {
-        parse(File::open(path)?)
-    }
+    read_sniffed(File::open(path)?, parse)
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::FIGfont;
+    use crate::{
+        error::{Error, ParseErrorKind},
+        utils::test_font,
+        Codetag, Encoding, FIGfont, FontFormat, ParseOptions,
+    };
 
     #[test]
     fn default() {
         assert!(FIGfont::standard().is_ok());
     }
+
+    #[test]
+    fn toilet() {
+        let mut font = test_font("tlf2a$ 1 1 4 -1 1\ntiny").replace("@@\n", "\u{2588}@@\n");
+        font.push_str("0x263A smile\n\u{263a}@@\n");
+
+        let font = FIGfont::read_from(font.as_bytes()).unwrap();
+        assert_eq!(font.header().format(), FontFormat::Toilet);
+        assert_eq!(
+            font.render("A\u{e4}\u{263a}").unwrap().to_string(),
+            "A\u{2588}\u{e4}\u{2588}\u{263a}"
+        );
+        assert_eq!(font.try_get(0x263a).unwrap().comment(), Some("smile"));
+
+        let mut written = Vec::new();
+        font.write_to(&mut written).unwrap();
+        let written = String::from_utf8(written).unwrap();
+        assert!(written.starts_with("tlf2a$ 1 1 4 -1 1 0\ntiny\n"));
+        assert!(written.contains("\n@\u{2588}##\n"));
+        assert!(written.ends_with("\n0x263A smile\n\u{263a}@@\n"));
+        assert_eq!(FIGfont::read_from(written.as_bytes()).unwrap(), font);
+
+        let font = FIGfont::standard().unwrap();
+        assert_eq!(font.header().format(), FontFormat::Figlet);
+    }
+
+    #[test]
+    fn parse_errors() {
+        let font = |header: &str, replace: &str, with: &str| {
+            let font = test_font(header).replacen(replace, with, 1);
+
+            match FIGfont::read_from(font.as_bytes()) {
+                Err(Error::Parse(error)) => error,
+                result => panic!("expected a parse error, got {:?}", result),
+            }
+        };
+
+        let error = font("flf2a$ 2 x 3 -1 0", "", "");
+        assert_eq!(error.kind(), ParseErrorKind::InvalidNumber(2));
+        assert_eq!((error.line(), error.column(), error.offset()), (1, 10, 9));
+        assert_eq!(error.codetag(), None);
+
+        let error = font("flf2a$ 2 2 3 -1 0", "B@@", "B@#");
+        assert_eq!(
+            error.kind(),
+            ParseErrorKind::InconsistentEndmark {
+                expected: '@',
+                found: '#'
+            }
+        );
+        assert_eq!((error.line(), error.column(), error.offset()), (71, 3, 261));
+        assert_eq!(error.codetag(), Some(Codetag::Char('B')));
+        assert_eq!(
+            error.to_string(),
+            "inconsistent endmark: expected '@', found '#' at line 71, column 3 \
+             in FIGcharacter 0x0042"
+        );
+
+        let error = font("flf2a$ 2 2 3 -1 0", "C@@\n", "\n");
+        assert_eq!(error.kind(), ParseErrorKind::MissingEndmark);
+        assert_eq!(error.line(), 73);
+
+        let error = font("flf2a$ 2 2 3 -1 0 0 0 1", "", "");
+        assert_eq!(
+            error.kind(),
+            ParseErrorKind::CodetagCount {
+                expected: 1,
+                found: 0
+            }
+        );
+
+        let error = font("flf2a$ 2 2 3 -1 0", "\u{df}@\n\u{df}@@\n", "\u{df}@\n");
+        assert_eq!(error.kind(), ParseErrorKind::UnexpectedEof);
+        assert_eq!(error.codetag(), Some(Codetag::Char('\u{df}')));
+    }
+
+    #[test]
+    fn lenient() {
+        let mut font = test_font("flf2a$ 2 2 3 -1 0 0 0 3 9");
+        font.push_str("xyz\nx@\nx@@\n0x263A\n\u{263a}@\n\u{263a}@@\n");
+        let font = font.replacen("B@@", "B#", 1);
+        let options = ParseOptions {
+            strict: false,
+            ..Default::default()
+        };
+
+        assert!(FIGfont::read_from(font.as_bytes()).is_err());
+        let (parsed, warnings) =
+            FIGfont::read_from_with_warnings(font.as_bytes(), &options).unwrap();
+        assert_eq!(
+            warnings
+                .iter()
+                .map(|warning| warning.kind())
+                .collect::<Vec<_>>(),
+            [
+                ParseErrorKind::HeaderFieldCount(10),
+                ParseErrorKind::InconsistentEndmark {
+                    expected: '@',
+                    found: '#'
+                },
+                ParseErrorKind::InvalidCodetag,
+                ParseErrorKind::CodetagCount {
+                    expected: 3,
+                    found: 2
+                },
+            ]
+        );
+        assert_eq!(
+            parsed.render("B\u{263a}").unwrap().to_lines(),
+            ["B\u{263a}"; 2]
+        );
+        assert_eq!(parsed.codetags().count(), 95 + 7 + 1);
+
+        let truncated = font.split("\u{df}@@").next().unwrap();
+        let (parsed, warnings) =
+            FIGfont::read_from_with_warnings(truncated.as_bytes(), &options).unwrap();
+        let warning = warnings.last().unwrap();
+        assert_eq!(warning.kind(), ParseErrorKind::UnexpectedEof);
+        assert_eq!(warning.codetag(), Some(Codetag::Char('\u{df}')));
+        assert!(parsed.contains('\u{fc}') && !parsed.contains('\u{df}'));
+    }
+
+    #[test]
+    fn encoding() {
+        // Latin-1, a byte per character.
+        let font: Vec<u8> = test_font("flf2a$ 1 1 3 -1 0")
+            .replace("@@\n", "\u{db}@@\n")
+            .chars()
+            .map(|c| c as u8)
+            .collect();
+
+        let font_with = |encoding| {
+            FIGfont::read_from_with(
+                &font[..],
+                &ParseOptions {
+                    encoding,
+                    ..Default::default()
+                },
+            )
+            .unwrap()
+        };
+
+        let latin1 = FIGfont::read_from(&font[..]).unwrap();
+        assert_eq!(latin1.encoding(), Encoding::Latin1);
+        assert_eq!(FIGfont::standard().unwrap().encoding(), Encoding::Latin1);
+        assert_eq!(latin1.render("A").unwrap().to_string(), "A\u{db}");
+
+        let cp437 = font_with(Encoding::Cp437);
+        assert_eq!(cp437.encoding(), Encoding::Cp437);
+        assert_eq!(cp437.render("A").unwrap().to_string(), "A\u{2588}");
+        assert_eq!(
+            cp437.render("\u{e4}").unwrap().to_string(),
+            "\u{3a3}\u{2588}"
+        );
+
+        assert!(FIGfont::read_from_with(
+            &font[..],
+            &ParseOptions {
+                encoding: Encoding::Utf8,
+                ..Default::default()
+            }
+        )
+        .is_err());
+
+        let utf8 = FIGfont::read_from(String::from_utf8_lossy(&font).as_bytes()).unwrap();
+        assert_eq!(utf8.encoding(), Encoding::Utf8);
+        assert_eq!(utf8.render("A").unwrap().to_string(), "A\u{fffd}");
+    }
+
+    #[cfg(feature = "zip")]
+    #[test]
+    fn zip_entries() {
+        let mut entries = FIGfont::zip_entries("fonts/bundle.zip").unwrap();
+        entries.sort();
+        assert_eq!(entries, ["extra/small.flf", "slant.flf"]);
+
+        let small = FIGfont::load_from_zip_entry("fonts/bundle.zip", "extra/small.flf").unwrap();
+        assert_eq!(small, FIGfont::load_from("fonts/plain/small.flf").unwrap());
+        assert!(FIGfont::load_from_zip_entry("fonts/bundle.zip", "small.flf").is_err());
+        assert!(FIGfont::load_from_zip_entry("fonts/bundle.zip", "README").is_err());
+    }
+
+    #[test]
+    fn sniff() {
+        let plain = FIGfont::load_from("fonts/plain/slant.flf").unwrap();
+        let bytes = std::fs::read("fonts/plain/slant.flf").unwrap();
+        assert_eq!(FIGfont::from_bytes(&bytes).unwrap(), plain);
+
+        let mut reader = std::io::Cursor::new([&b"junk"[..], &bytes].concat());
+        reader.set_position(4);
+        assert_eq!(FIGfont::read_from_seekable(reader).unwrap(), plain);
+
+        let zipped = std::fs::read("fonts/zipped/slant.flf").unwrap();
+        if cfg!(feature = "zip") {
+            assert_eq!(FIGfont::from_bytes(&zipped).unwrap(), plain);
+
+            let file = std::fs::File::open("fonts/bundle.zip").unwrap();
+            assert_eq!(FIGfont::read_from_seekable(file).unwrap(), plain);
+        } else {
+            assert!(FIGfont::from_bytes(&zipped).is_err());
+        }
+
+        assert!(FIGfont::from_bytes(b"").is_err());
+        assert!(FIGfont::from_bytes(b"PK").is_err());
+    }
+
+    #[test]
+    fn compressed() {
+        let slant = FIGfont::load_from("fonts/compressed/slant.flf.gz");
+        let small = FIGfont::load_from("fonts/compressed/small.flf.zst");
+        let gzipped = std::fs::read("fonts/compressed/slant.flf.gz").unwrap();
+        let zstd = std::fs::read("fonts/compressed/small.flf.zst").unwrap();
+
+        if cfg!(feature = "gzip") {
+            let plain = FIGfont::load_from("fonts/plain/slant.flf").unwrap();
+            assert_eq!(slant.unwrap(), plain);
+            assert_eq!(FIGfont::from_bytes(&gzipped).unwrap(), plain);
+            assert!(FIGfont::from_bytes(&gzipped[..100]).is_err());
+        } else {
+            assert!(slant.is_err());
+            assert!(FIGfont::from_bytes(&gzipped).is_err());
+        }
+
+        if cfg!(feature = "zstd") {
+            let plain = FIGfont::load_from("fonts/plain/small.flf").unwrap();
+            assert_eq!(small.unwrap(), plain);
+            assert_eq!(FIGfont::from_bytes(&zstd).unwrap(), plain);
+        } else {
+            assert!(small.is_err());
+            assert!(FIGfont::from_bytes(&zstd).is_err());
+        }
+    }
+
+    #[test]
+    fn get_char() {
+        let font = FIGfont::standard().unwrap();
+
+        assert_eq!(font.get_char('A').lines(), font.get(65).lines());
+        assert_eq!(font.get_char('\u{20ac}').lines(), font.get(126).lines());
+        assert!(font.contains('\u{e4}'));
+        assert!(font.contains('\u{17f}'));
+        assert!(!font.contains('\u{20ac}'));
+        assert!(font.try_get_char('\u{20ac}').is_none());
+    }
+
+    #[test]
+    fn negative_codetags() {
+        let font = FIGfont::load_from("fonts/plain/big.flf").unwrap();
+
+        let negative: Vec<Codetag> = font.codetags().filter(|c| c.is_negative()).collect();
+        assert_eq!(negative, vec![Codetag::Negative(-5)]);
+        assert!(font.try_get(-5).is_some());
+        assert!(font
+            .codetags()
+            .all(|c| c.is_negative() == c.as_char().is_none()));
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut font = test_font("flf2a$ 1 1 5 -1 0 0 0 2");
+        font.push_str("0x41\nA2@@\n-0x80000000\nmin@@\n");
+        let font = FIGfont::read_from(font.as_bytes()).unwrap();
+        assert!(font.try_get(i32::MIN).is_some());
+
+        let mut written = Vec::new();
+        font.write_to(&mut written).unwrap();
+        let written = String::from_utf8(written).unwrap();
+        assert!(written.starts_with("flf2a$ 1 1 5 -1 0 0 0 2\n"));
+        assert!(written.contains("\n-0x80000000\nmin@@\n0x0041\nA2@@\n"));
+        assert_eq!(FIGfont::read_from(written.as_bytes()).unwrap(), font);
+    }
+
+    #[test]
+    fn lazy() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<FIGfont>();
+
+        let options = ParseOptions {
+            lazy: true,
+            ..Default::default()
+        };
+        let eager = FIGfont::load_from("fonts/plain/big.flf").unwrap();
+        let font = FIGfont::load_from_with("fonts/plain/big.flf", &options).unwrap();
+
+        let expected = eager.render("Hello").unwrap().to_lines();
+        std::thread::scope(|scope| {
+            for _ in 0..4 {
+                scope.spawn(|| assert_eq!(font.render("Hello").unwrap().to_lines(), expected));
+            }
+        });
+        assert_eq!(font.try_get(-5), eager.try_get(-5));
+        assert_eq!(font, eager);
+
+        let mut written = Vec::new();
+        font.write_to(&mut written).unwrap();
+        assert_eq!(FIGfont::read_from(&written[..]).unwrap(), eager);
+
+        let truncated = std::fs::read("fonts/plain/big.flf").unwrap();
+        let truncated = &truncated[..truncated.len() / 2];
+        assert!(FIGfont::read_from_with(truncated, &options).is_err());
+
+        let latin1 = test_font("flf2a$ 1 1 3 -1 0").replace("A@@", "A\u{e9}@@");
+        let latin1: Vec<u8> = latin1.chars().map(|c| c as u8).collect();
+        let utf8 = ParseOptions {
+            encoding: Encoding::Utf8,
+            ..options
+        };
+        match FIGfont::read_from_with(&latin1[..], &utf8) {
+            Err(Error::Parse(error)) => {
+                assert_eq!(
+                    error.kind(),
+                    ParseErrorKind::InvalidEncoding(Encoding::Utf8)
+                );
+                assert_eq!(error.codetag(), Some(Codetag::Char('A')));
+            }
+            result => panic!("expected a parse error, got {:?}", result),
+        }
+
+        let lenient = ParseOptions {
+            strict: false,
+            ..utf8
+        };
+        let (font, warnings) = FIGfont::read_from_with_warnings(&latin1[..], &lenient).unwrap();
+        // 'A' and the Deutsch characters.
+        assert_eq!(warnings.len(), 1 + 7);
+        assert!(warnings
+            .iter()
+            .all(|warning| warning.kind() == ParseErrorKind::InvalidEncoding(Encoding::Utf8)));
+        assert_eq!(font.render("A").unwrap().to_string(), "A\u{fffd}");
+    }
 }
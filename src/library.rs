@@ -0,0 +1,292 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    env, fs,
+    io::{Error, ErrorKind},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+use crate::{font_file_names, font_name, result::Result, FIGfont};
+
+/// Environment variable with the FIGlet font directory.
+const FONTDIR_VARIABLE: &str = "FIGLET_FONTDIR";
+
+/// Directories where FIGlet and TOIlet fonts are usually installed.
+const SYSTEM_DIRECTORIES: [&str; 6] = [
+    "/usr/share/figlet",
+    "/usr/local/share/figlet",
+    "/usr/share/figlet/fonts",
+    "/usr/local/share/figlet/fonts",
+    "/opt/homebrew/share/figlet/fonts",
+    "/usr/share/toilet",
+];
+
+/// A collection of fonts found by name in a list of directories.
+///
+/// Loaded fonts are cached by canonical path, so loading the same font twice
+/// (even through different names) returns the same `Arc`.
+#[derive(Debug)]
+pub struct FontLibrary {
+    directories: Vec<PathBuf>,
+    cache: Mutex<HashMap<PathBuf, Arc<FIGfont>>>,
+    archived: BTreeMap<String, (PathBuf, Arc<FIGfont>)>,
+}
+
+impl FontLibrary {
+    /// Create a library searching the default directories.
+    pub fn new() -> Self {
+        Self::with_directories(Self::default_directories())
+    }
+
+    /// Create a library searching only `directories`, in order.
+    pub fn with_directories<I, P>(directories: I) -> Self
+    where
+        I: IntoIterator<Item = P>,
+        P: Into<PathBuf>,
+    {
+        FontLibrary {
+            directories: directories.into_iter().map(Into::into).collect(),
+            cache: Mutex::new(HashMap::new()),
+            archived: BTreeMap::new(),
+        }
+    }
+
+    /// Get the default directories: `FIGLET_FONTDIR`, if set, then the
+    /// common system locations.
+    pub fn default_directories() -> Vec<PathBuf> {
+        env::var_os(FONTDIR_VARIABLE)
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from)
+            .into_iter()
+            .chain(SYSTEM_DIRECTORIES.iter().map(PathBuf::from))
+            .collect()
+    }
+
+    /// Get the searched directories.
+    pub fn directories(&self) -> &[PathBuf] {
+        &self.directories
+    }
+
+    /// Add a directory at the end of the search list.
+    pub fn push_directory<P: Into<PathBuf>>(&mut self, directory: P) {
+        self.directories.push(directory.into());
+    }
+
+    /// Find the file of the font `name`, with or without extension. Names
+    /// with a directory are paths and aren't searched. Compressed fonts are
+    /// found too if you have the gzip or zstd feature enabled.
+    pub fn find(&self, name: &str) -> Option<PathBuf> {
+        let path = Path::new(name);
+        if path.parent().is_some_and(|parent| parent != Path::new("")) {
+            return find_in(None, path);
+        }
+
+        self.directories
+            .iter()
+            .find_map(|directory| find_in(Some(directory), path))
+    }
+
+    /// Load every font of a zip archive into the library, returning how many
+    /// were added. They are named after their entries, without directory
+    /// and extension, and take precedence over the fonts in the directories.
+    /// When more entries have the same name, the first one loaded wins.
+    #[cfg(feature = "zip")]
+    pub fn load_zip<P: AsRef<Path>>(&mut self, path: P) -> Result<usize> {
+        let path = fs::canonicalize(path)?;
+        let mut added = 0;
+
+        for entry in FIGfont::zip_entries(&path)? {
+            let name = match font_name(Path::new(&entry)) {
+                Some(name) => name.to_string(),
+                None => continue,
+            };
+            if let std::collections::btree_map::Entry::Vacant(vacant) = self.archived.entry(name) {
+                let font = Arc::new(FIGfont::load_from_zip_entry(&path, &entry)?);
+                vacant.insert((path.join(&entry), font));
+                added += 1;
+            }
+        }
+
+        Ok(added)
+    }
+
+    /// Load the font `name`, or get it from the cache.
+    pub fn load(&self, name: &str) -> Result<Arc<FIGfont>> {
+        if let Some((_, font)) = self.archived(name) {
+            return Ok(Arc::clone(font));
+        }
+
+        let path = self
+            .find(name)
+            .ok_or_else(|| Error::new(ErrorKind::NotFound, format!("font {} not found", name)))?;
+        let path = fs::canonicalize(path)?;
+
+        if let Some(font) = self.cache.lock().unwrap().get(&path) {
+            return Ok(Arc::clone(font));
+        }
+
+        let font = Arc::new(FIGfont::load_from(&path)?);
+        Ok(Arc::clone(
+            self.cache.lock().unwrap().entry(path).or_insert(font),
+        ))
+    }
+
+    /// List the available fonts by name. When more directories have a font
+    /// with the same name, the first one wins.
+    pub fn fonts(&self) -> BTreeMap<String, PathBuf> {
+        let mut fonts: BTreeMap<String, PathBuf> = self
+            .archived
+            .iter()
+            .map(|(name, (path, _))| (name.clone(), path.clone()))
+            .collect();
+
+        for directory in self.directories.iter() {
+            let entries = match fs::read_dir(directory) {
+                Ok(entries) => entries,
+                Err(_) => continue,
+            };
+
+            for entry in entries.flatten() {
+                let path = entry.path();
+                if !path.is_file() {
+                    continue;
+                }
+
+                if let Some(name) = font_name(&path) {
+                    fonts.entry(name.to_string()).or_insert(path);
+                }
+            }
+        }
+
+        fonts
+    }
+
+    fn archived(&self, name: &str) -> Option<&(PathBuf, Arc<FIGfont>)> {
+        let path = Path::new(name);
+        if path.parent().is_some_and(|parent| parent != Path::new("")) {
+            return None;
+        }
+
+        self.archived.get(font_name(path).unwrap_or(name))
+    }
+}
+
+impl Default for FontLibrary {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn is_font(path: &Path) -> bool {
+    path.is_file() && font_name(path).is_some()
+}
+
+fn find_in(directory: Option<&Path>, name: &Path) -> Option<PathBuf> {
+    let path = match directory {
+        Some(directory) => directory.join(name),
+        None => name.to_path_buf(),
+    };
+
+    if is_font(&path) {
+        return Some(path);
+    }
+
+    let name = path.file_name()?.to_str()?;
+    font_file_names(name)
+        .into_iter()
+        .map(|file_name| path.with_file_name(file_name))
+        .find(|path| is_font(path))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::FontLibrary;
+
+    #[test]
+    fn load() {
+        let library = FontLibrary::with_directories(["does/not/exist", "fonts/plain"]);
+
+        let slant = library.load("slant").unwrap();
+        assert!(Arc::ptr_eq(&slant, &library.load("slant.flf").unwrap()));
+        assert!(Arc::ptr_eq(
+            &slant,
+            &library.load("fonts/plain/slant").unwrap()
+        ));
+        assert!(!Arc::ptr_eq(&slant, &library.load("standard").unwrap()));
+
+        assert!(library.load("nonexistent").is_err());
+        assert!(library.load("plain/slant").is_err());
+        assert!(FontLibrary::with_directories(["fonts"])
+            .load("slant")
+            .is_err());
+    }
+
+    #[test]
+    fn fonts() {
+        let library = FontLibrary::with_directories(["fonts/plain"]);
+        let fonts = library.fonts();
+
+        assert_eq!(fonts.len(), 18);
+        assert!(fonts["standard"].ends_with("fonts/plain/standard.flf"));
+        assert!(FontLibrary::with_directories(["does/not/exist"])
+            .fonts()
+            .is_empty());
+    }
+
+    #[cfg(feature = "zip")]
+    #[test]
+    fn load_zip() {
+        let mut library = FontLibrary::with_directories(["fonts/plain"]);
+        let standard = library.load("standard").unwrap();
+
+        assert_eq!(library.load_zip("fonts/bundle.zip").unwrap(), 2);
+        let small = library.load("small").unwrap();
+        assert!(Arc::ptr_eq(&small, &library.load("small.flf").unwrap()));
+        assert!(!Arc::ptr_eq(
+            &small,
+            &library.load("fonts/plain/small").unwrap()
+        ));
+        assert!(Arc::ptr_eq(&standard, &library.load("standard").unwrap()));
+
+        let fonts = library.fonts();
+        assert_eq!(fonts.len(), 18);
+        assert!(fonts["small"].ends_with("fonts/bundle.zip/extra/small.flf"));
+        assert!(fonts["slant"].ends_with("fonts/bundle.zip/slant.flf"));
+
+        assert_eq!(library.load_zip("fonts/bundle.zip").unwrap(), 0);
+        assert!(Arc::ptr_eq(&small, &library.load("small").unwrap()));
+
+        assert_eq!(library.load_zip("fonts/duplicates.zip").unwrap(), 1);
+        assert!(library.fonts()["mini"].ends_with("fonts/duplicates.zip/mini.flf"));
+        assert_eq!(
+            *library.load("mini").unwrap(),
+            crate::FIGfont::load_from("fonts/plain/mini.flf").unwrap()
+        );
+
+        assert!(library.load_zip("fonts/plain/slant.flf").is_err());
+    }
+
+    #[test]
+    fn compressed() {
+        let library = FontLibrary::with_directories(["fonts/compressed"]);
+        let fonts = library.fonts();
+
+        assert_eq!(fonts.contains_key("slant"), cfg!(feature = "gzip"));
+        assert_eq!(fonts.contains_key("small"), cfg!(feature = "zstd"));
+        assert_eq!(library.load("slant").is_ok(), cfg!(feature = "gzip"));
+        assert_eq!(library.load("small.flf").is_ok(), cfg!(feature = "zstd"));
+    }
+
+    #[test]
+    fn default_directories() {
+        let library = FontLibrary::new();
+
+        assert!(library
+            .directories()
+            .iter()
+            .any(|dir| dir.ends_with("share/figlet")));
+    }
+}
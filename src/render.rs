@@ -0,0 +1,1074 @@
+use crate::{
+    character::FIGcharacter,
+    codetag::Codetag,
+    control::ControlFile,
+    error::Error,
+    figure::FIGure,
+    header::{Layout, PrintDirection},
+    result::Result,
+    smushing,
+    subcharacter::SubCharacter,
+    FIGfont,
+};
+
+const HORIZONTAL: Layout = Layout::from_bits_truncate(0xff);
+const VERTICAL: Layout = Layout::from_bits_truncate(0xff00);
+
+/// How the layout of a single axis is chosen when rendering.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LayoutMode {
+    /// Use the font's layout (figlet's `-s`).
+    #[default]
+    Font,
+    /// Full width or full height, no overlapping at all (figlet's `-W`).
+    Full,
+    /// Slide the FIGcharacters until they touch (figlet's `-k`).
+    Fitting,
+    /// Smush with the font's rules, or universally if the font has none
+    /// (figlet's `-S`).
+    Smushing,
+    /// Universal smushing, ignoring the font's rules (figlet's `-o`).
+    Overlapping,
+    /// Use the axis' bits of an explicit layout (figlet's `-m`).
+    Explicit(Layout),
+}
+
+impl LayoutMode {
+    fn apply(self, font: Layout, axis: Layout, fitting: Layout, smush: Layout) -> Layout {
+        match self {
+            LayoutMode::Font => font & axis,
+            LayoutMode::Full => Layout::empty(),
+            LayoutMode::Fitting => fitting,
+            LayoutMode::Smushing => (font & axis) | smush,
+            LayoutMode::Overlapping => smush,
+            LayoutMode::Explicit(layout) => layout & axis,
+        }
+    }
+}
+
+/// Horizontal alignment of the rendered lines.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Justification {
+    /// Left-justify when printing left to right, right-justify otherwise
+    /// (figlet's `-x`).
+    #[default]
+    Auto,
+    /// Left-justify (figlet's `-l`).
+    Left,
+    /// Center (figlet's `-c`).
+    Center,
+    /// Right-justify (figlet's `-r`).
+    Right,
+}
+
+/// What to do with characters the font doesn't have.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MissingCharacter {
+    /// Use the font's missing character (code 0), skip the character if the
+    /// font doesn't have one.
+    #[default]
+    Missing,
+    /// Use the FIGcharacter with the given code, skip the character if the
+    /// font doesn't have it either.
+    Replace(i32),
+    /// Skip the character.
+    Skip,
+    /// Fail with `Error::UnsupportedCharacters`.
+    Error,
+}
+
+/// Options to tweak the rendering of a FIGure.
+#[derive(Debug, Clone, Default)]
+pub struct RenderOptions {
+    /// How to lay out FIGcharacters next to each other.
+    pub horizontal_layout: LayoutMode,
+    /// How to lay out lines of FIGcharacters on top of each other.
+    pub vertical_layout: LayoutMode,
+    /// The print direction, `None` to use the font's one.
+    pub direction: Option<PrintDirection>,
+    /// The maximum width (in columns) of the output. Longer lines are
+    /// wrapped at word boundaries, or inside words wider than the limit.
+    pub width: Option<usize>,
+    /// How to align the lines within the output width, or within the widest
+    /// line if there's no maximum width.
+    pub justification: Justification,
+    /// What to do with characters the font doesn't have.
+    pub missing_character: MissingCharacter,
+    /// Control files remapping the input characters, applied in order, each
+    /// one to the output of the previous (like figlet's `-C`).
+    pub control_files: Vec<ControlFile>,
+}
+
+impl RenderOptions {
+    /// Get the layout to use when rendering with a font whose layout is
+    /// `font`.
+    pub fn layout(&self, font: Layout) -> Layout {
+        self.horizontal_layout.apply(
+            font,
+            HORIZONTAL,
+            Layout::HORIZONTAL_KERNING,
+            Layout::HORIZONTAL_SMUSH,
+        ) | self.vertical_layout.apply(
+            font,
+            VERTICAL,
+            Layout::VERTICAL_KERNING,
+            Layout::VERTICAL_SMUSH,
+        )
+    }
+}
+
+/// Renders text into FIGures using a FIGfont.
+#[derive(Debug, Clone)]
+pub struct Renderer<'a> {
+    font: &'a FIGfont,
+    options: RenderOptions,
+}
+
+impl<'a> Renderer<'a> {
+    /// Create a new renderer for `font`.
+    pub fn new(font: &'a FIGfont) -> Self {
+        Self::with_options(font, RenderOptions::default())
+    }
+
+    /// Create a new renderer for `font` with custom options.
+    pub fn with_options(font: &'a FIGfont, options: RenderOptions) -> Self {
+        Renderer { font, options }
+    }
+
+    /// Get the FIGfont used by the renderer.
+    pub fn font(&self) -> &'a FIGfont {
+        self.font
+    }
+
+    /// Get the renderer's options.
+    pub fn options(&self) -> &RenderOptions {
+        &self.options
+    }
+
+    /// Get the layout actually used by the renderer.
+    pub fn layout(&self) -> Layout {
+        self.options.layout(self.font.header().layout())
+    }
+
+    /// Get the print direction actually used by the renderer.
+    pub fn direction(&self) -> PrintDirection {
+        self.options
+            .direction
+            .unwrap_or_else(|| self.font.header().print_direction())
+    }
+
+    /// Get the justification actually used by the renderer.
+    pub fn justification(&self) -> Justification {
+        match (self.options.justification, self.direction()) {
+            (Justification::Auto, PrintDirection::LeftToRight) => Justification::Left,
+            (Justification::Auto, PrintDirection::RightToLeft) => Justification::Right,
+            (justification, _) => justification,
+        }
+    }
+
+    /// Get the code `c` is mapped to by the control files.
+    pub fn translate(&self, c: char) -> i32 {
+        self.options
+            .control_files
+            .iter()
+            .fold(Codetag::from(c).code(), |code, control| {
+                control.translate(code)
+            })
+    }
+
+    /// Get the FIGcharacter used to render `c`, if any.
+    pub fn get(&self, c: char) -> Option<&'a FIGcharacter> {
+        self.font
+            .try_get(self.translate(c))
+            .or_else(|| match self.options.missing_character {
+                MissingCharacter::Missing => self.font.try_get(0),
+                MissingCharacter::Replace(code) => self.font.try_get(code),
+                MissingCharacter::Skip | MissingCharacter::Error => None,
+            })
+    }
+
+    /// Render `text`. Every line of `text` becomes a row of FIGcharacters.
+    ///
+    /// Fails only if some characters are missing from the font and the
+    /// missing character policy is `MissingCharacter::Error`.
+    pub fn render(&self, text: &str) -> Result<FIGure> {
+        if self.options.missing_character == MissingCharacter::Error {
+            let mut unsupported = Vec::new();
+            for c in text.chars().filter_map(filter_char) {
+                if self.font.try_get(self.translate(c)).is_none() && !unsupported.contains(&c) {
+                    unsupported.push(c);
+                }
+            }
+
+            if !unsupported.is_empty() {
+                return Err(Error::UnsupportedCharacters(unsupported.into_boxed_slice()));
+            }
+        }
+
+        let header = self.font.header();
+        let layout = self.layout();
+        let direction = self.direction();
+
+        let new_line = || Line::new(header.height(), layout, direction);
+
+        let mut lines = Vec::new();
+        for line in text.lines() {
+            self.wrap(line, new_line, &mut lines);
+        }
+
+        let width = self
+            .options
+            .width
+            .unwrap_or_else(|| lines.iter().map(Line::width).max().unwrap_or_default());
+        let justification = self.justification();
+
+        let mut rows: Vec<Vec<SubCharacter>> = Vec::new();
+        for line in lines {
+            let padding = width.saturating_sub(line.width());
+            let padding = match justification {
+                Justification::Center => padding / 2,
+                Justification::Right => padding,
+                _ => 0,
+            };
+
+            let mut line = line.rows;
+            if padding != 0 {
+                for row in line.iter_mut() {
+                    row.splice(0..0, vec![SubCharacter::Symbol(" ".into()); padding]);
+                }
+            }
+            stack(&mut rows, line, layout);
+        }
+
+        Ok(FIGure::new(rows))
+    }
+
+    /// Render the characters of `chars`, like `render`.
+    pub fn render_chars<I: IntoIterator<Item = char>>(&self, chars: I) -> Result<FIGure> {
+        self.render(&chars.into_iter().collect::<String>())
+    }
+
+    /// Render a single line of `text` into `lines`, wrapping it if it's wider
+    /// than the maximum width. Lines break at whitespace, and inside a word
+    /// only if the word alone is too wide.
+    fn wrap<F: Fn() -> Line<'a>>(&self, text: &str, new_line: F, lines: &mut Vec<Line<'a>>) {
+        let mut line = new_line();
+        let mut blank = String::new();
+        let mut wrapped = false;
+
+        for word in words(text) {
+            if word.starts_with(char::is_whitespace) {
+                blank.push_str(word);
+                continue;
+            }
+
+            let start = line.len();
+            let skip_blank = wrapped && line.is_empty();
+            let fits =
+                (skip_blank || self.push_str(&mut line, &blank)) && self.push_str(&mut line, word);
+            blank.clear();
+            if fits {
+                continue;
+            }
+
+            line.truncate(start);
+            if !line.is_empty() {
+                lines.push(std::mem::replace(&mut line, new_line()));
+                wrapped = true;
+
+                if self.push_str(&mut line, word) {
+                    continue;
+                }
+                line.truncate(0);
+            }
+
+            for c in word.chars() {
+                let len = line.len();
+                self.push_char(&mut line, c);
+                if len > 0 && !self.fits(&line) {
+                    line.truncate(len);
+                    lines.push(std::mem::replace(&mut line, new_line()));
+                    wrapped = true;
+                    self.push_char(&mut line, c);
+                }
+            }
+        }
+
+        if !blank.is_empty() && (!wrapped || !line.is_empty()) {
+            let len = line.len();
+            if !self.push_str(&mut line, &blank) {
+                line.truncate(len);
+            }
+        }
+
+        lines.push(line);
+    }
+
+    fn fits(&self, line: &Line) -> bool {
+        self.options.width.is_none_or(|width| line.width() <= width)
+    }
+
+    /// Push `text` into `line` while it fits, returning whether all of it did.
+    fn push_str(&self, line: &mut Line<'a>, text: &str) -> bool {
+        for c in text.chars() {
+            self.push_char(line, c);
+            if !self.fits(line) {
+                return false;
+            }
+        }
+
+        true
+    }
+
+    fn push_char(&self, line: &mut Line<'a>, c: char) {
+        if let Some(character) = filter_char(c).and_then(|c| self.get(c)) {
+            line.push(character);
+        }
+    }
+}
+
+/// Split `text` into words and runs of whitespace.
+fn words(text: &str) -> impl Iterator<Item = &str> {
+    let mut rest = text;
+    std::iter::from_fn(move || {
+        let space = rest.chars().next()?.is_whitespace();
+        let end = rest
+            .find(|c: char| c.is_whitespace() != space)
+            .unwrap_or(rest.len());
+        let (word, tail) = rest.split_at(end);
+        rest = tail;
+        Some(word)
+    })
+}
+
+/// Sum the output columns of `subs`.
+fn columns(subs: &[SubCharacter]) -> usize {
+    subs.iter().map(SubCharacter::width).sum()
+}
+
+/// Turn tabs into spaces and drop the other control characters.
+fn filter_char(c: char) -> Option<char> {
+    match c {
+        '\t' => Some(' '),
+        c if c.is_control() => None,
+        c => Some(c),
+    }
+}
+
+/// Stack `line` under `rows`, fitting or smushing them vertically.
+fn stack(rows: &mut Vec<Vec<SubCharacter>>, line: Vec<Vec<SubCharacter>>, layout: Layout) {
+    let amount = vertical_overlap(rows, &line, layout);
+    let start = rows.len() - amount;
+
+    let mut line = line.into_iter();
+    for (top, bottom) in rows[start..].iter_mut().zip(line.by_ref()) {
+        if top.len() < bottom.len() {
+            top.resize(bottom.len(), SubCharacter::Symbol(" ".into()));
+        }
+
+        for (cell, sub) in top.iter_mut().zip(bottom) {
+            if let Some(smushed) = smushing::vertical(cell, &sub, layout) {
+                *cell = smushed;
+            }
+        }
+    }
+    rows.extend(line);
+}
+
+/// Compute how many rows of `line` can overlap the last rows of `rows`.
+fn vertical_overlap(
+    rows: &[Vec<SubCharacter>],
+    line: &[Vec<SubCharacter>],
+    layout: Layout,
+) -> usize {
+    if !layout.intersects(Layout::VERTICAL_SMUSH | Layout::VERTICAL_KERNING) {
+        return 0;
+    }
+
+    let mut overlap = 0;
+
+    for amount in 1..=rows.len().min(line.len()) {
+        let mut last = false;
+
+        for (top, bottom) in rows[(rows.len() - amount)..].iter().zip(line) {
+            for (t, b) in top.iter().zip(bottom) {
+                if t.is_space() || b.is_space() {
+                    continue;
+                }
+
+                if layout.contains(Layout::VERTICAL_SMUSH | Layout::VERTICAL_BIGX)
+                    && smushing::is_supersmush(t, b)
+                {
+                    continue;
+                }
+
+                if smushing::vertical(t, b, layout).is_none() {
+                    return overlap;
+                }
+
+                last = true;
+            }
+        }
+
+        overlap = amount;
+        if last {
+            break;
+        }
+    }
+
+    overlap
+}
+
+/// A single line of FIGcharacters being rendered.
+struct Line<'a> {
+    rows: Vec<Vec<SubCharacter>>,
+    /// The width of each row, in output columns.
+    columns: Vec<usize>,
+    layout: Layout,
+    direction: PrintDirection,
+    characters: Vec<&'a FIGcharacter>,
+    previous_width: usize,
+    current_width: usize,
+}
+
+impl<'a> Line<'a> {
+    fn new(height: usize, layout: Layout, direction: PrintDirection) -> Self {
+        Line {
+            rows: vec![Vec::new(); height],
+            columns: vec![0; height],
+            layout,
+            direction,
+            characters: Vec::new(),
+            previous_width: 0,
+            current_width: 0,
+        }
+    }
+
+    /// Get the width of the line, in output columns.
+    fn width(&self) -> usize {
+        self.columns.iter().copied().max().unwrap_or_default()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.characters.is_empty()
+    }
+
+    /// Get the number of FIGcharacters in the line.
+    fn len(&self) -> usize {
+        self.characters.len()
+    }
+
+    /// Keep only the first `len` FIGcharacters, laying them out again.
+    fn truncate(&mut self, len: usize) {
+        if len >= self.len() {
+            return;
+        }
+
+        let characters = std::mem::take(&mut self.characters);
+        *self = Line::new(self.rows.len(), self.layout, self.direction);
+        for character in characters.into_iter().take(len) {
+            self.push(character);
+        }
+    }
+
+    fn push(&mut self, character: &'a FIGcharacter) {
+        self.characters.push(character);
+        self.previous_width = self.current_width;
+        self.current_width = character.width();
+
+        let amount = self.overlap(character);
+
+        for i in 0..self.rows.len() {
+            let line = character.lines().get(i).map(AsRef::as_ref).unwrap_or(&[]);
+            let row = std::mem::take(&mut self.rows[i]);
+            let amount = match self.direction {
+                PrintDirection::LeftToRight => amount.min(line.len()),
+                PrintDirection::RightToLeft => amount.min(row.len()),
+            };
+
+            // Only the smushed columns and the new ones change.
+            let (row, removed, added) = match self.direction {
+                PrintDirection::LeftToRight => {
+                    let kept = row.len().saturating_sub(amount);
+                    let removed = columns(&row[kept..]);
+                    let row = self.merge(row, line, amount);
+                    let added = columns(&row[kept..]);
+                    (row, removed, added)
+                }
+                PrintDirection::RightToLeft => {
+                    let removed = columns(&row[..amount]);
+                    let kept = row.len() - amount;
+                    let row = self.merge(line.to_vec(), &row, amount);
+                    let added = columns(&row[..row.len() - kept]);
+                    (row, removed, added)
+                }
+            };
+            self.columns[i] = self.columns[i] - removed + added;
+            self.rows[i] = row;
+        }
+    }
+
+    /// Append `right` to `left`, smushing `amount` columns together.
+    fn merge(
+        &self,
+        left: Vec<SubCharacter>,
+        right: &[SubCharacter],
+        amount: usize,
+    ) -> Vec<SubCharacter> {
+        let amount = amount.min(right.len());
+        let width = left.len();
+        let mut res = left;
+
+        for (k, sub) in right[..amount].iter().enumerate() {
+            let column = (width + k).saturating_sub(amount);
+            match res.get(column) {
+                Some(cell) => {
+                    if let Some(smushed) = self.smush(cell, sub) {
+                        res[column] = smushed;
+                    }
+                }
+                None if !sub.is_space() => res.push(sub.clone()),
+                None => (),
+            }
+        }
+
+        res.extend(right[amount..].iter().cloned());
+        res
+    }
+
+    /// Compute how many columns `character` can slide into the line, row by
+    /// row, stopping where the glyphs touch or where they can't be smushed.
+    fn overlap(&self, character: &FIGcharacter) -> usize {
+        if !self
+            .layout
+            .intersects(Layout::HORIZONTAL_SMUSH | Layout::HORIZONTAL_KERNING)
+        {
+            return 0;
+        }
+
+        let width = self.rows.iter().map(Vec::len).max().unwrap_or_default();
+        let mut overlap = self.current_width;
+
+        for (i, row) in self.rows.iter().enumerate() {
+            let line = character.lines().get(i).map(AsRef::as_ref).unwrap_or(&[]);
+
+            let amount = match self.direction {
+                PrintDirection::LeftToRight => self.row_overlap(row, width, line),
+                PrintDirection::RightToLeft => {
+                    overlap = overlap.min(row.len());
+                    self.row_overlap(line, self.current_width, row)
+                }
+            };
+
+            overlap = overlap.min(amount);
+        }
+
+        overlap
+    }
+
+    /// Compute how many columns `right` can slide into `left` on a single row.
+    fn row_overlap(&self, left: &[SubCharacter], width: usize, right: &[SubCharacter]) -> usize {
+        let mut end = left.len();
+        while end > 0 && left.get(end).is_none_or(SubCharacter::is_space) {
+            end -= 1;
+        }
+        let l = left.get(end);
+
+        let start = right.iter().take_while(|sub| sub.is_space()).count();
+        let r = right.get(start);
+
+        let mut amount = (start + width) as isize - 1 - end as isize;
+        match (l, r) {
+            (None, _) => amount += 1,
+            (Some(l), _) if l.is_space() => amount += 1,
+            (Some(l), Some(r)) if self.smush(l, r).is_some() => amount += 1,
+            _ => (),
+        }
+
+        amount.max(0) as usize
+    }
+
+    fn smush(&self, left: &SubCharacter, right: &SubCharacter) -> Option<SubCharacter> {
+        if left.is_space() {
+            return Some(right.clone());
+        }
+        if right.is_space() {
+            return Some(left.clone());
+        }
+
+        if self.previous_width < 2 || self.current_width < 2 {
+            return None;
+        }
+
+        smushing::horizontal(left, right, self.layout, self.direction)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Justification, LayoutMode, MissingCharacter, RenderOptions};
+    use crate::{error::Error, utils::test_font, ControlFile, FIGfont, Layout, PrintDirection};
+
+    fn tiny_font(missing: Option<char>) -> FIGfont {
+        let mut font = test_font("flf2a$ 1 1 3 -1 1\ntiny");
+        if let Some(c) = missing {
+            font.push_str(&format!("0 missing\n{}@@\n", c));
+        }
+
+        FIGfont::read_from(font.as_bytes()).unwrap()
+    }
+
+    #[test]
+    fn smushing() {
+        let font = FIGfont::standard().unwrap();
+
+        assert_eq!(
+            font.render("Hello").unwrap().to_string(),
+            [
+                " _   _      _ _       ",
+                "| | | | ___| | | ___  ",
+                "| |_| |/ _ \\ | |/ _ \\ ",
+                "|  _  |  __/ | | (_) |",
+                "|_| |_|\\___|_|_|\\___/ ",
+                "                      ",
+            ]
+            .join("\n")
+        );
+    }
+
+    #[test]
+    fn fitting() {
+        let font = FIGfont::load_from("fonts/plain/slant.flf").unwrap();
+
+        assert_eq!(
+            font.render("Hello").unwrap().to_string(),
+            [
+                "    __  __     ____    ",
+                "   / / / /__  / / /___ ",
+                "  / /_/ / _ \\/ / / __ \\",
+                " / __  /  __/ / / /_/ /",
+                "/_/ /_/\\___/_/_/\\____/ ",
+                "                       ",
+            ]
+            .join("\n")
+        );
+
+        let font = FIGfont::load_from("fonts/plain/small.flf").unwrap();
+
+        assert_eq!(
+            font.render("Hello").unwrap().to_string(),
+            [
+                " _  _     _ _     ",
+                "| || |___| | |___ ",
+                "| __ / -_) | / _ \\",
+                "|_||_\\___|_|_\\___/",
+                "                  ",
+            ]
+            .join("\n")
+        );
+    }
+
+    #[test]
+    fn layout_override() {
+        let font = FIGfont::standard().unwrap();
+        let render = |mode| {
+            let options = RenderOptions {
+                horizontal_layout: mode,
+                ..Default::default()
+            };
+            font.render_with("Hi", &options).unwrap().to_string()
+        };
+
+        assert_eq!(
+            render(LayoutMode::Full),
+            [
+                "  _   _   _ ",
+                " | | | | (_)",
+                " | |_| | | |",
+                " |  _  | | |",
+                " |_| |_| |_|",
+                "            ",
+            ]
+            .join("\n")
+        );
+        assert_eq!(
+            render(LayoutMode::Fitting),
+            [
+                " _   _  _ ",
+                "| | | |(_)",
+                "| |_| || |",
+                "|  _  || |",
+                "|_| |_||_|",
+                "          ",
+            ]
+            .join("\n")
+        );
+        assert_eq!(
+            render(LayoutMode::Smushing),
+            font.render("Hi").unwrap().to_string()
+        );
+        assert_eq!(
+            render(LayoutMode::Explicit(Layout::HORIZONTAL_KERNING)),
+            render(LayoutMode::Fitting)
+        );
+        assert_eq!(
+            render(LayoutMode::Overlapping),
+            [
+                " _   _ _ ",
+                "| | | (_)",
+                "| |_| | |",
+                "|  _  | |",
+                "|_| |_|_|",
+                "         ",
+            ]
+            .join("\n")
+        );
+    }
+
+    #[test]
+    fn resolve_layout() {
+        let font = Layout::HORIZONTAL_EQUAL | Layout::HORIZONTAL_SMUSH | Layout::VERTICAL_KERNING;
+        let options = |horizontal_layout, vertical_layout| RenderOptions {
+            horizontal_layout,
+            vertical_layout,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            options(LayoutMode::Font, LayoutMode::Font).layout(font),
+            font
+        );
+        assert_eq!(
+            options(LayoutMode::Full, LayoutMode::Font).layout(font),
+            Layout::VERTICAL_KERNING
+        );
+        assert_eq!(
+            options(LayoutMode::Overlapping, LayoutMode::Full).layout(font),
+            Layout::HORIZONTAL_SMUSH
+        );
+        assert_eq!(
+            options(LayoutMode::Font, LayoutMode::Smushing).layout(font),
+            font | Layout::VERTICAL_SMUSH
+        );
+        assert_eq!(
+            options(LayoutMode::Explicit(Layout::all()), LayoutMode::Font).layout(font),
+            (Layout::all() & super::HORIZONTAL) | Layout::VERTICAL_KERNING
+        );
+    }
+
+    #[test]
+    fn multiple_lines() {
+        let font = FIGfont::standard().unwrap();
+        let render = |mode| {
+            let options = RenderOptions {
+                vertical_layout: mode,
+                ..Default::default()
+            };
+            font.render_with("Hi\nHi", &options).unwrap().to_string()
+        };
+
+        let full = render(LayoutMode::Full);
+        assert_eq!(full.lines().count(), 12);
+        assert_eq!(
+            full,
+            format!(
+                "{}\n{}",
+                font.render("Hi").unwrap(),
+                font.render("Hi").unwrap()
+            )
+        );
+
+        assert_eq!(
+            render(LayoutMode::Fitting),
+            [
+                " _   _ _ ",
+                "| | | (_)",
+                "| |_| | |",
+                "|  _  | |",
+                "|_| |_|_|",
+                " _   _ _ ",
+                "| | | (_)",
+                "| |_| | |",
+                "|  _  | |",
+                "|_| |_|_|",
+                "         ",
+            ]
+            .join("\n")
+        );
+
+        assert_eq!(
+            render(LayoutMode::Font),
+            [
+                " _   _ _ ",
+                "| | | (_)",
+                "| |_| | |",
+                "|  _  | |",
+                "|_| |_|_|",
+                "| | | (_)",
+                "| |_| | |",
+                "|  _  | |",
+                "|_| |_|_|",
+                "         ",
+            ]
+            .join("\n")
+        );
+    }
+
+    #[test]
+    fn vertical_bigx() {
+        let font = FIGfont::read_from(test_font("flf2a$ 1 1 3 -1 0").as_bytes()).unwrap();
+        let render = |text, layout| {
+            let options = RenderOptions {
+                vertical_layout: LayoutMode::Explicit(layout),
+                ..Default::default()
+            };
+            font.render_with(text, &options).unwrap().to_lines()
+        };
+
+        let smushing = Layout::VERTICAL_SMUSH | Layout::VERTICAL_BIGX;
+        assert_eq!(render("|\n|", smushing), ["|"]);
+
+        // Fitting never supersmushes, whatever the rules.
+        let fitting = Layout::VERTICAL_KERNING | Layout::VERTICAL_BIGX;
+        assert_eq!(render("|\n|", fitting), ["|", "|"]);
+        assert_eq!(render("/\n\\", fitting), ["/", "\\"]);
+    }
+
+    #[test]
+    fn right_to_left() {
+        let font = FIGfont::load_from("fonts/plain/ivrit.flf").unwrap();
+
+        assert_eq!(
+            font.render("ab\nHello").unwrap().to_string(),
+            [
+                "           _           ",
+                "          | |__   __ _ ",
+                "          | '_ \\ / _` |",
+                "          | |_) | (_| |",
+                "          |_.__/ \\__,_|",
+                "        _ _      _   _ ",
+                "   ___ | | | ___| | | |",
+                "  / _ \\| | |/ _ \\ |_| |",
+                " | (_) | | |  __/  _  |",
+                "  \\___/|_|_|\\___|_| |_|",
+                "                       ",
+            ]
+            .join("\n")
+        );
+
+        let options = RenderOptions {
+            direction: Some(PrintDirection::LeftToRight),
+            ..Default::default()
+        };
+        assert_eq!(
+            font.render_with("Hello", &options).unwrap().to_string(),
+            [
+                " _   _      _ _       ",
+                "| | | | ___| | | ___  ",
+                "| |_| |/ _ \\ | |/ _ \\ ",
+                "|  _  |  __/ | | (_) |",
+                "|_| |_|\\___|_|_|\\___/ ",
+                "                      ",
+            ]
+            .join("\n")
+        );
+    }
+
+    #[test]
+    fn wrapping() {
+        let font = FIGfont::standard().unwrap();
+        let options = |width| RenderOptions {
+            width: Some(width),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            font.render_with("Hello world", &options(80))
+                .unwrap()
+                .to_string(),
+            font.render("Hello world").unwrap().to_string()
+        );
+        assert_eq!(
+            font.render_with("Hello world", &options(30))
+                .unwrap()
+                .to_string(),
+            font.render("Hello\nworld").unwrap().to_string()
+        );
+        assert_eq!(
+            font.render_with("Hello  big world", &options(40))
+                .unwrap()
+                .to_string(),
+            font.render("Hello  big\nworld").unwrap().to_string()
+        );
+
+        let wrapped = font.render_with("Hello", &options(15)).unwrap().to_string();
+        assert_eq!(wrapped, font.render("Hel\nlo").unwrap().to_string());
+        assert!(wrapped.lines().all(|row| row.chars().count() <= 15));
+    }
+
+    #[test]
+    fn wrapping_punctuation() {
+        let font = FIGfont::standard().unwrap();
+        let options = |width| RenderOptions {
+            width: Some(width),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            font.render_with("Hello, you", &options(30))
+                .unwrap()
+                .to_string(),
+            font.render("Hello,\nyou").unwrap().to_string()
+        );
+        assert_eq!(
+            font.render_with("foo-bar baz", &options(45))
+                .unwrap()
+                .to_string(),
+            font.render("foo-bar\nbaz").unwrap().to_string()
+        );
+
+        let font = tiny_font(Some('\u{4e00}'));
+        let options = RenderOptions {
+            width: Some(4),
+            missing_character: MissingCharacter::Missing,
+            ..Default::default()
+        };
+        assert_eq!(
+            font.render_with("\u{20ac}\u{20ac}\u{20ac}", &options)
+                .unwrap()
+                .to_string(),
+            "\u{4e00}\u{4e00}\n\u{4e00}"
+        );
+    }
+
+    #[test]
+    fn justification() {
+        let font = FIGfont::standard().unwrap();
+        let options = |justification, width| RenderOptions {
+            justification,
+            width,
+            ..Default::default()
+        };
+        let hi = font.render("Hi").unwrap().to_string();
+        let pad = |n: usize| {
+            hi.lines()
+                .map(|row| format!("{}{}", " ".repeat(n), row))
+                .collect::<Vec<_>>()
+                .join("\n")
+        };
+
+        assert_eq!(
+            font.render_with("Hi", &options(Justification::Left, Some(20)))
+                .unwrap()
+                .to_string(),
+            hi
+        );
+        assert_eq!(
+            font.render_with("Hi", &options(Justification::Auto, Some(20)))
+                .unwrap()
+                .to_string(),
+            hi
+        );
+        assert_eq!(
+            font.render_with("Hi", &options(Justification::Center, Some(20)))
+                .unwrap()
+                .to_string(),
+            pad(5)
+        );
+        assert_eq!(
+            font.render_with("Hi", &options(Justification::Right, Some(20)))
+                .unwrap()
+                .to_string(),
+            pad(11)
+        );
+
+        let rtl = RenderOptions {
+            direction: Some(PrintDirection::RightToLeft),
+            ..options(Justification::Auto, Some(20))
+        };
+        let rendered = font.render_with("Hi", &rtl).unwrap().to_string();
+        assert!(rendered.lines().all(|row| row.chars().count() == 20));
+
+        let rendered = font
+            .render_with("Hi\nHello", &options(Justification::Right, None))
+            .unwrap()
+            .to_string();
+        assert!(rendered.lines().all(|row| row.chars().count() == 22));
+    }
+
+    #[test]
+    fn missing_character() {
+        let options = |missing_character| RenderOptions {
+            missing_character,
+            ..Default::default()
+        };
+        let render = |font: &FIGfont, missing| {
+            font.render_with("a\u{20ac}b\u{1f600}", &options(missing))
+                .map(|figure| figure.to_string())
+        };
+
+        let font = tiny_font(Some('#'));
+        assert_eq!(render(&font, MissingCharacter::Missing).unwrap(), "a#b#");
+        assert_eq!(
+            render(&font, MissingCharacter::Replace(126)).unwrap(),
+            "a~b~"
+        );
+        assert_eq!(render(&font, MissingCharacter::Skip).unwrap(), "ab");
+        match render(&font, MissingCharacter::Error) {
+            Err(Error::UnsupportedCharacters(chars)) => {
+                assert_eq!(&*chars, &['\u{20ac}', '\u{1f600}'])
+            }
+            res => panic!("unexpected {:?}", res),
+        }
+
+        let font = tiny_font(None);
+        assert_eq!(render(&font, MissingCharacter::Missing).unwrap(), "ab");
+        assert_eq!(render(&font, MissingCharacter::Replace(-1)).unwrap(), "ab");
+        assert_eq!(font.get(0x20ac).lines(), font.get(126).lines());
+        assert!(font.try_get(0x20ac).is_none());
+    }
+
+    #[test]
+    fn chars() {
+        let font = FIGfont::standard().unwrap();
+        let renderer = super::Renderer::new(&font);
+
+        assert_eq!(
+            renderer.render_chars("Hello".chars().rev()).unwrap(),
+            font.render("olleH").unwrap()
+        );
+    }
+
+    #[test]
+    fn control_files() {
+        let font = FIGfont::standard().unwrap();
+        let upper = ControlFile::read_from(&b"flc2a\nt a-z A-Z\n"[..]).unwrap();
+        let swap = ControlFile::read_from(&b"flc2a\nt H I\nt I H\n"[..]).unwrap();
+
+        let options = RenderOptions {
+            control_files: vec![upper.clone()],
+            ..Default::default()
+        };
+        assert_eq!(
+            font.render_with("hi", &options).unwrap(),
+            font.render("HI").unwrap()
+        );
+
+        let options = RenderOptions {
+            control_files: vec![upper, swap],
+            missing_character: MissingCharacter::Error,
+            ..Default::default()
+        };
+        assert_eq!(
+            font.render_with("hi", &options).unwrap(),
+            font.render("IH").unwrap()
+        );
+    }
+
+    #[test]
+    fn empty() {
+        let font = FIGfont::standard().unwrap();
+
+        assert_eq!(font.render("").unwrap().to_string(), "");
+    }
+}
@@ -0,0 +1,419 @@
+use crate::{
+    header::{Layout, PrintDirection},
+    subcharacter::SubCharacter,
+};
+
+const HORIZONTAL_RULES: Layout = Layout::HORIZONTAL_EQUAL
+    .union(Layout::HORIZONTAL_LOWLINE)
+    .union(Layout::HORIZONTAL_HIERARCHY)
+    .union(Layout::HORIZONTAL_PAIR)
+    .union(Layout::HORIZONTAL_BIGX)
+    .union(Layout::HORIZONTAL_HARDBLANK);
+
+const VERTICAL_RULES: Layout = Layout::VERTICAL_EQUAL
+    .union(Layout::VERTICAL_LOWLINE)
+    .union(Layout::VERTICAL_HIERARCHY)
+    .union(Layout::VERTICAL_PAIR)
+    .union(Layout::VERTICAL_BIGX);
+
+const HIERARCHY: [&str; 6] = ["|", "/\\", "[]", "{}", "()", "<>"];
+
+/// Horizontally smush the `left` SubCharacter with the `right` one following
+/// the `layout` rules.
+///
+/// Returns the resulting SubCharacter, or `None` if the two SubCharacters
+/// can't be smushed together. Spaces always give way to the other
+/// SubCharacter. When no controlled smushing rule is set universal smushing
+/// is applied: the latter character in the text (the right one, or the left
+/// one when printing right to left) wins.
+pub fn horizontal(
+    left: &SubCharacter,
+    right: &SubCharacter,
+    layout: Layout,
+    direction: PrintDirection,
+) -> Option<SubCharacter> {
+    if left.is_space() {
+        return Some(right.clone());
+    }
+    if right.is_space() {
+        return Some(left.clone());
+    }
+
+    if !layout.contains(Layout::HORIZONTAL_SMUSH) {
+        return None;
+    }
+
+    if !layout.intersects(HORIZONTAL_RULES) {
+        if left.is_blank() {
+            return Some(right.clone());
+        }
+        if right.is_blank() {
+            return Some(left.clone());
+        }
+
+        return Some(match direction {
+            PrintDirection::LeftToRight => right.clone(),
+            PrintDirection::RightToLeft => left.clone(),
+        });
+    }
+
+    if left.is_blank() || right.is_blank() {
+        return if layout.contains(Layout::HORIZONTAL_HARDBLANK) && left == right {
+            Some(SubCharacter::Blank)
+        } else {
+            None
+        };
+    }
+
+    let (l, r) = match (symbol(left), symbol(right)) {
+        (Some(l), Some(r)) => (l, r),
+        _ => {
+            return if layout.contains(Layout::HORIZONTAL_EQUAL) && left == right {
+                Some(left.clone())
+            } else {
+                None
+            };
+        }
+    };
+
+    if layout.contains(Layout::HORIZONTAL_EQUAL) && l == r {
+        return Some(left.clone());
+    }
+
+    if layout.contains(Layout::HORIZONTAL_LOWLINE) {
+        if let Some(c) = lowline(l, r) {
+            return Some(c.into());
+        }
+    }
+
+    if layout.contains(Layout::HORIZONTAL_HIERARCHY) {
+        if let Some(c) = hierarchy(l, r) {
+            return Some(c.into());
+        }
+    }
+
+    if layout.contains(Layout::HORIZONTAL_PAIR) && is_pair(l, r) {
+        return Some('|'.into());
+    }
+
+    if layout.contains(Layout::HORIZONTAL_BIGX) {
+        match (l, r) {
+            ('/', '\\') => return Some('|'.into()),
+            ('\\', '/') => return Some('Y'.into()),
+            ('>', '<') => return Some('X'.into()),
+            _ => (),
+        }
+    }
+
+    None
+}
+
+/// Vertically smush the `top` SubCharacter with the `bottom` one following
+/// the `layout` rules.
+///
+/// Returns the resulting SubCharacter, or `None` if the two SubCharacters
+/// can't be smushed together. Spaces always give way to the other
+/// SubCharacter. When no controlled smushing rule is set universal smushing
+/// is applied and the bottom character wins.
+pub fn vertical(top: &SubCharacter, bottom: &SubCharacter, layout: Layout) -> Option<SubCharacter> {
+    if top.is_space() {
+        return Some(bottom.clone());
+    }
+    if bottom.is_space() {
+        return Some(top.clone());
+    }
+
+    if !layout.contains(Layout::VERTICAL_SMUSH) {
+        return None;
+    }
+
+    if !layout.intersects(VERTICAL_RULES) {
+        return Some(if bottom.is_blank() {
+            top.clone()
+        } else {
+            bottom.clone()
+        });
+    }
+
+    let (t, b) = match (symbol(top), symbol(bottom)) {
+        (Some(t), Some(b)) => (t, b),
+        _ => {
+            return if layout.contains(Layout::VERTICAL_EQUAL) && !top.is_blank() && top == bottom {
+                Some(top.clone())
+            } else {
+                None
+            };
+        }
+    };
+
+    if layout.contains(Layout::VERTICAL_EQUAL) && t == b {
+        return Some(top.clone());
+    }
+
+    if layout.contains(Layout::VERTICAL_LOWLINE) {
+        if let Some(c) = lowline(t, b) {
+            return Some(c.into());
+        }
+    }
+
+    if layout.contains(Layout::VERTICAL_HIERARCHY) {
+        if let Some(c) = hierarchy(t, b) {
+            return Some(c.into());
+        }
+    }
+
+    if layout.contains(Layout::VERTICAL_PAIR) && matches!((t, b), ('-', '_') | ('_', '-')) {
+        return Some('='.into());
+    }
+
+    if layout.contains(Layout::VERTICAL_BIGX) && is_supersmush(top, bottom) {
+        return Some('|'.into());
+    }
+
+    None
+}
+
+/// Check if `top` and `bottom` are two vertical lines, which can be
+/// supersmushed when the `Layout::VERTICAL_BIGX` rule is set.
+pub(crate) fn is_supersmush(top: &SubCharacter, bottom: &SubCharacter) -> bool {
+    symbol(top) == Some('|') && symbol(bottom) == Some('|')
+}
+
+fn symbol(sub: &SubCharacter) -> Option<char> {
+    match sub {
+        SubCharacter::Symbol(sym) => {
+            let mut chars = sym.chars();
+            match (chars.next(), chars.next()) {
+                (Some(c), None) => Some(c),
+                _ => None,
+            }
+        }
+        SubCharacter::Blank => None,
+    }
+}
+
+fn lowline(a: char, b: char) -> Option<char> {
+    const REPLACERS: &str = "|/\\[]{}()<>";
+
+    if a == '_' && REPLACERS.contains(b) {
+        Some(b)
+    } else if b == '_' && REPLACERS.contains(a) {
+        Some(a)
+    } else {
+        None
+    }
+}
+
+fn hierarchy(a: char, b: char) -> Option<char> {
+    let class = |c: char| HIERARCHY.iter().position(|class| class.contains(c));
+
+    match (class(a), class(b)) {
+        (Some(x), Some(y)) if x < y => Some(b),
+        (Some(x), Some(y)) if x > y => Some(a),
+        _ => None,
+    }
+}
+
+fn is_pair(a: char, b: char) -> bool {
+    matches!(
+        (a, b),
+        ('[', ']') | (']', '[') | ('{', '}') | ('}', '{') | ('(', ')') | (')', '(')
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{horizontal, vertical};
+    use crate::{
+        header::{Layout, PrintDirection},
+        subcharacter::SubCharacter,
+    };
+
+    fn smush(left: &str, right: &str, layout: Layout) -> Option<String> {
+        smush_in(left, right, layout, PrintDirection::LeftToRight)
+    }
+
+    fn smush_in(
+        left: &str,
+        right: &str,
+        layout: Layout,
+        direction: PrintDirection,
+    ) -> Option<String> {
+        let sub = |s: &str| {
+            if s == "$" {
+                SubCharacter::Blank
+            } else {
+                SubCharacter::Symbol(s.into())
+            }
+        };
+
+        horizontal(&sub(left), &sub(right), layout, direction).map(|c| match c {
+            SubCharacter::Blank => "$".to_string(),
+            SubCharacter::Symbol(s) => s.into(),
+        })
+    }
+
+    fn rule(rule: Layout) -> Layout {
+        rule | Layout::HORIZONTAL_SMUSH
+    }
+
+    #[test]
+    fn spaces() {
+        let layout = Layout::HORIZONTAL_KERNING;
+        assert_eq!(smush(" ", "|", layout).as_deref(), Some("|"));
+        assert_eq!(smush("|", " ", layout).as_deref(), Some("|"));
+        assert_eq!(smush("|", "|", layout), None);
+    }
+
+    #[test]
+    fn equal() {
+        let layout = rule(Layout::HORIZONTAL_EQUAL);
+        assert_eq!(smush("|", "|", layout).as_deref(), Some("|"));
+        assert_eq!(smush("/", "/", layout).as_deref(), Some("/"));
+        assert_eq!(smush("|", "/", layout), None);
+        assert_eq!(smush("$", "$", layout), None);
+    }
+
+    #[test]
+    fn lowline() {
+        let layout = rule(Layout::HORIZONTAL_LOWLINE);
+        for c in ["|", "/", "\\", "[", "]", "{", "}", "(", ")", "<", ">"] {
+            assert_eq!(smush("_", c, layout).as_deref(), Some(c));
+            assert_eq!(smush(c, "_", layout).as_deref(), Some(c));
+        }
+        assert_eq!(smush("_", "_", layout), None);
+        assert_eq!(smush("_", "a", layout), None);
+    }
+
+    #[test]
+    fn hierarchy() {
+        let layout = rule(Layout::HORIZONTAL_HIERARCHY);
+        assert_eq!(smush("|", "/", layout).as_deref(), Some("/"));
+        assert_eq!(smush("\\", "|", layout).as_deref(), Some("\\"));
+        assert_eq!(smush("/", "]", layout).as_deref(), Some("]"));
+        assert_eq!(smush("[", "{", layout).as_deref(), Some("{"));
+        assert_eq!(smush("}", "(", layout).as_deref(), Some("("));
+        assert_eq!(smush(")", "<", layout).as_deref(), Some("<"));
+        assert_eq!(smush(">", "|", layout).as_deref(), Some(">"));
+        assert_eq!(smush("/", "\\", layout), None);
+        assert_eq!(smush("(", ")", layout), None);
+    }
+
+    #[test]
+    fn pair() {
+        let layout = rule(Layout::HORIZONTAL_PAIR);
+        assert_eq!(smush("[", "]", layout).as_deref(), Some("|"));
+        assert_eq!(smush("]", "[", layout).as_deref(), Some("|"));
+        assert_eq!(smush("{", "}", layout).as_deref(), Some("|"));
+        assert_eq!(smush("}", "{", layout).as_deref(), Some("|"));
+        assert_eq!(smush("(", ")", layout).as_deref(), Some("|"));
+        assert_eq!(smush(")", "(", layout).as_deref(), Some("|"));
+        assert_eq!(smush("<", ">", layout), None);
+    }
+
+    #[test]
+    fn big_x() {
+        let layout = rule(Layout::HORIZONTAL_BIGX);
+        assert_eq!(smush("/", "\\", layout).as_deref(), Some("|"));
+        assert_eq!(smush("\\", "/", layout).as_deref(), Some("Y"));
+        assert_eq!(smush(">", "<", layout).as_deref(), Some("X"));
+        assert_eq!(smush("<", ">", layout), None);
+    }
+
+    #[test]
+    fn hardblank() {
+        let layout = rule(Layout::HORIZONTAL_HARDBLANK);
+        assert_eq!(smush("$", "$", layout).as_deref(), Some("$"));
+        assert_eq!(smush("$", "|", layout), None);
+
+        let layout = rule(Layout::HORIZONTAL_EQUAL);
+        assert_eq!(smush("$", "$", layout), None);
+    }
+
+    #[test]
+    fn universal() {
+        let layout = Layout::HORIZONTAL_SMUSH;
+        assert_eq!(smush("a", "b", layout).as_deref(), Some("b"));
+        assert_eq!(smush("$", "b", layout).as_deref(), Some("b"));
+        assert_eq!(smush("a", "$", layout).as_deref(), Some("a"));
+        assert_eq!(
+            smush_in("a", "b", layout, PrintDirection::RightToLeft).as_deref(),
+            Some("a")
+        );
+    }
+
+    fn vsmush(top: &str, bottom: &str, layout: Layout) -> Option<String> {
+        let sub = |s: &str| {
+            if s == "$" {
+                SubCharacter::Blank
+            } else {
+                SubCharacter::Symbol(s.into())
+            }
+        };
+
+        vertical(&sub(top), &sub(bottom), layout).map(|c| match c {
+            SubCharacter::Blank => "$".to_string(),
+            SubCharacter::Symbol(s) => s.into(),
+        })
+    }
+
+    fn vrule(rule: Layout) -> Layout {
+        rule | Layout::VERTICAL_SMUSH
+    }
+
+    #[test]
+    fn vertical_fitting() {
+        let layout = Layout::VERTICAL_KERNING;
+        assert_eq!(vsmush(" ", "_", layout).as_deref(), Some("_"));
+        assert_eq!(vsmush("_", " ", layout).as_deref(), Some("_"));
+        assert_eq!(vsmush("_", "_", layout), None);
+    }
+
+    #[test]
+    fn vertical_equal() {
+        let layout = vrule(Layout::VERTICAL_EQUAL);
+        assert_eq!(vsmush("_", "_", layout).as_deref(), Some("_"));
+        assert_eq!(vsmush("_", "-", layout), None);
+        assert_eq!(vsmush("$", "$", layout), None);
+    }
+
+    #[test]
+    fn vertical_lowline() {
+        let layout = vrule(Layout::VERTICAL_LOWLINE);
+        assert_eq!(vsmush("_", "|", layout).as_deref(), Some("|"));
+        assert_eq!(vsmush("/", "_", layout).as_deref(), Some("/"));
+        assert_eq!(vsmush("_", "-", layout), None);
+    }
+
+    #[test]
+    fn vertical_hierarchy() {
+        let layout = vrule(Layout::VERTICAL_HIERARCHY);
+        assert_eq!(vsmush("|", "/", layout).as_deref(), Some("/"));
+        assert_eq!(vsmush("{", "[", layout).as_deref(), Some("{"));
+        assert_eq!(vsmush("<", ")", layout).as_deref(), Some("<"));
+        assert_eq!(vsmush("/", "\\", layout), None);
+    }
+
+    #[test]
+    fn horizontal_line() {
+        let layout = vrule(Layout::VERTICAL_PAIR);
+        assert_eq!(vsmush("-", "_", layout).as_deref(), Some("="));
+        assert_eq!(vsmush("_", "-", layout).as_deref(), Some("="));
+        assert_eq!(vsmush("-", "-", layout), None);
+    }
+
+    #[test]
+    fn vertical_line() {
+        let layout = vrule(Layout::VERTICAL_BIGX);
+        assert_eq!(vsmush("|", "|", layout).as_deref(), Some("|"));
+        assert_eq!(vsmush("|", "/", layout), None);
+    }
+
+    #[test]
+    fn vertical_universal() {
+        let layout = Layout::VERTICAL_SMUSH;
+        assert_eq!(vsmush("a", "b", layout).as_deref(), Some("b"));
+        assert_eq!(vsmush("a", "$", layout).as_deref(), Some("a"));
+        assert_eq!(vsmush("$", "b", layout).as_deref(), Some("b"));
+    }
+}
@@ -1,10 +1,9 @@
 use std::{
     borrow::Borrow,
     fmt::{Display, Formatter},
-    str,
 };
 
-use encoding::{all::ISO_8859_1, Encoding};
+use crate::{charset::Encoding, error::ParseErrorKind};
 use unicode_segmentation::UnicodeSegmentation;
 use unicode_width::UnicodeWidthStr;
 
@@ -40,10 +39,7 @@ fn split_at<'a>(haystack: &'a [u8], when: &[u8]) -> (&'a [u8], Option<&'a [u8]>)
         match memchr::memchr(when[0], &haystack[off..]).map(|n| n + off) {
             Some(i) => {
                 if let Some(rest) = haystack[i..].strip_prefix(when) {
-                    return (
-                        &haystack[..i],
-                        if rest.is_empty() { None } else { Some(rest) },
-                    );
+                    return (&haystack[..i], Some(rest));
                 }
                 off = i + 1;
             }
@@ -75,27 +71,74 @@ fn split<'a, 'b>(haystack: &'a [u8], when: &'b [u8]) -> SplitWith<'a, 'b> {
     }
 }
 
-impl SubCharacter {
-    /// Split a Latin1-encoded string in a Vec<SubCharacter>
-    pub fn split(raw: &[u8], blank_character: &[u8]) -> Result<Vec<SubCharacter>, String> {
-        let mut res = Vec::new();
-        for (i, string) in split(raw, blank_character).enumerate() {
-            if i != 0 {
-                res.push(SubCharacter::Blank);
-            }
+fn split_decode<F>(
+    raw: &[u8],
+    blank_character: &[u8],
+    decode: F,
+) -> Result<Vec<SubCharacter>, ParseErrorKind>
+where
+    F: Fn(&[u8]) -> Result<String, ParseErrorKind>,
+{
+    let mut res = Vec::new();
+    for (i, string) in split(raw, blank_character).enumerate() {
+        if i != 0 {
+            res.push(SubCharacter::Blank);
+        }
 
-            if !string.is_empty() {
-                let string = ISO_8859_1
-                    .decode(string, encoding::DecoderTrap::Strict)
-                    .map_err(|e| e.to_string())?
-                    .to_string();
-                for g in string.graphemes(false) {
-                    res.push(SubCharacter::Symbol(g.to_string().into_boxed_str()));
-                }
+        if !string.is_empty() {
+            for g in decode(string)?.graphemes(false) {
+                res.push(SubCharacter::Symbol(g.to_string().into_boxed_str()));
             }
         }
+    }
+
+    Ok(res)
+}
+
+impl SubCharacter {
+    /// Split a Latin1-encoded string in a Vec<SubCharacter>
+    pub fn split(raw: &[u8], blank_character: &[u8]) -> Result<Vec<SubCharacter>, ParseErrorKind> {
+        Self::split_with(raw, blank_character, Encoding::Latin1)
+    }
 
-        Ok(res)
+    /// Split a UTF-8-encoded string in a Vec<SubCharacter>
+    pub fn split_utf8(
+        raw: &[u8],
+        blank_character: &[u8],
+    ) -> Result<Vec<SubCharacter>, ParseErrorKind> {
+        Self::split_with(raw, blank_character, Encoding::Utf8)
+    }
+
+    /// Split a string in the given encoding in a Vec<SubCharacter>. Fails
+    /// with `ParseErrorKind::InvalidEncoding` if it isn't valid in it.
+    pub fn split_with(
+        raw: &[u8],
+        blank_character: &[u8],
+        encoding: Encoding,
+    ) -> Result<Vec<SubCharacter>, ParseErrorKind> {
+        split_decode(raw, blank_character, |string| {
+            encoding
+                .decode(string)
+                .map_err(|_| ParseErrorKind::InvalidEncoding(encoding))
+        })
+    }
+
+    /// Check if `split_with` would succeed, without splitting.
+    pub(crate) fn is_valid(raw: &[u8], blank_character: &[u8], encoding: Encoding) -> bool {
+        split(raw, blank_character).all(|string| encoding.is_valid(string))
+    }
+
+    /// Split a string in the given encoding in a Vec<SubCharacter>,
+    /// replacing what isn't valid in it.
+    pub(crate) fn split_lossy(
+        raw: &[u8],
+        blank_character: &[u8],
+        encoding: Encoding,
+    ) -> Vec<SubCharacter> {
+        split_decode(raw, blank_character, |string| {
+            Ok(encoding.decode_lossy(string))
+        })
+        .unwrap_or_default()
     }
 
     /// Get the width (number of terminal cells) of the SubCharacter.
@@ -106,6 +149,11 @@ impl SubCharacter {
         }
     }
 
+    /// Check if it is a space character.
+    pub fn is_space(&self) -> bool {
+        matches!(self, SubCharacter::Symbol(ref sym) if sym.as_ref() == " ")
+    }
+
     /// Check if it is an hard blank character.
     pub fn is_blank(&self) -> bool {
         matches!(self, SubCharacter::Blank)
@@ -150,3 +198,39 @@ impl From<char> for SubCharacter {
         From::from(&c)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::SubCharacter;
+    use crate::{charset::Encoding, error::ParseErrorKind};
+
+    #[test]
+    fn split() {
+        let sym = |s: &str| SubCharacter::Symbol(s.into());
+
+        assert_eq!(
+            SubCharacter::split(b"a$b", b"$").unwrap(),
+            vec![sym("a"), SubCharacter::Blank, sym("b")]
+        );
+        assert_eq!(
+            SubCharacter::split(b" $", b"$").unwrap(),
+            vec![sym(" "), SubCharacter::Blank]
+        );
+        assert_eq!(
+            SubCharacter::split(b"$$", b"$").unwrap(),
+            vec![SubCharacter::Blank, SubCharacter::Blank]
+        );
+        assert_eq!(
+            SubCharacter::split("\u{e9}".as_bytes(), b"$").unwrap(),
+            vec![sym("\u{c3}"), sym("\u{a9}")]
+        );
+        assert_eq!(
+            SubCharacter::split_utf8("\u{2588}$\u{e9}".as_bytes(), b"$").unwrap(),
+            vec![sym("\u{2588}"), SubCharacter::Blank, sym("\u{e9}")]
+        );
+        assert_eq!(
+            SubCharacter::split_utf8(b"\xe9", b"$"),
+            Err(ParseErrorKind::InvalidEncoding(Encoding::Utf8))
+        );
+    }
+}
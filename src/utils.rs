@@ -1,31 +1,154 @@
-use crate::error::ParseError;
+use crate::error::{ParseError, ParseErrorKind};
 use crate::result::Result;
 use std::io::{BufRead, BufReader, Read};
 
-pub(crate) fn read_line<R: Read>(bread: &mut BufReader<R>) -> Result<Vec<u8>> {
-    let mut line = Vec::new();
-    bread.read_until(b'\n', &mut line)?;
+/// A buffered reader keeping track of lines and byte offsets, to tell where
+/// parse errors happen. When not strict, it collects the errors the parser
+/// recovers from as warnings.
+pub(crate) struct LineReader<R> {
+    inner: BufReader<R>,
+    line: usize,
+    offset: u64,
+    strict: bool,
+    lint: bool,
+    warnings: Vec<ParseError>,
+}
+
+impl<R: Read> LineReader<R> {
+    pub(crate) fn new(reader: R, strict: bool) -> Self {
+        LineReader {
+            inner: BufReader::new(reader),
+            line: 0,
+            offset: 0,
+            strict,
+            lint: false,
+            warnings: Vec::new(),
+        }
+    }
+
+    /// Also collect the problems which don't stop parsing, to validate a
+    /// font.
+    pub(crate) fn linting(mut self) -> Self {
+        self.lint = true;
+        self
+    }
+
+    /// Keep `error` as a warning if validating, ignore it otherwise.
+    pub(crate) fn lint(&mut self, error: ParseError) {
+        if self.lint {
+            self.warnings.push(error);
+        }
+    }
+
+    /// Fail with `error` if strict, otherwise keep it as a warning.
+    pub(crate) fn warn(&mut self, error: ParseError) -> Result<()> {
+        if self.strict {
+            return Err(error.into());
+        }
+
+        self.warnings.push(error);
+        Ok(())
+    }
+
+    /// Get the warnings collected so far.
+    pub(crate) fn into_warnings(self) -> Vec<ParseError> {
+        self.warnings
+    }
+
+    /// Get the line number and byte offset of the next line.
+    pub(crate) fn position(&self) -> (usize, u64) {
+        (self.line + 1, self.offset)
+    }
+
+    /// Create an error at the start of the next line.
+    pub(crate) fn error(&self, kind: ParseErrorKind) -> ParseError {
+        let (line, offset) = self.position();
+        ParseError::new(kind, line, offset)
+    }
+
+    /// Check if there is nothing left to read.
+    pub(crate) fn is_empty(&mut self) -> Result<bool> {
+        Ok(self.inner.fill_buf()?.is_empty())
+    }
 
+    /// Append the next line, with its line terminator, to `buf`. Returns the
+    /// number of bytes read.
+    pub(crate) fn read_raw_line(&mut self, buf: &mut Vec<u8>) -> Result<usize> {
+        let read = self.inner.read_until(b'\n', buf)?;
+        if read > 0 {
+            self.line += 1;
+            self.offset += read as u64;
+        }
+
+        Ok(read)
+    }
+
+    /// Read the next line, which must end with a line terminator.
+    pub(crate) fn read_line(&mut self) -> Result<Vec<u8>> {
+        let mut line = Vec::new();
+        self.read_line_into(&mut line)?;
+        Ok(line)
+    }
+
+    /// Read the next line into `line`, replacing its content. It must end
+    /// with a line terminator.
+    pub(crate) fn read_line_into(&mut self, line: &mut Vec<u8>) -> Result<()> {
+        let (number, offset) = self.position();
+        line.clear();
+        if self.read_raw_line(line)? == 0 {
+            return Err(ParseError::new(ParseErrorKind::UnexpectedEof, number, offset).into());
+        }
+
+        if !strip_terminator(line) {
+            let error = ParseError::new(ParseErrorKind::MissingNewline, number, offset);
+            self.warn(error.at_column(line.len() + 1))?;
+        }
+
+        Ok(())
+    }
+
+    /// Read the next line into `line`, replacing its content. It may be the
+    /// last one without a line terminator.
+    pub(crate) fn read_last_line_into(&mut self, line: &mut Vec<u8>) -> Result<()> {
+        let error = self.error(ParseErrorKind::UnexpectedEof);
+        line.clear();
+        if self.read_raw_line(line)? == 0 {
+            return Err(error.into());
+        }
+
+        strip_terminator(line);
+
+        Ok(())
+    }
+}
+
+/// Remove the trailing `\n` or `\r\n`, returning whether there was one.
+pub(crate) fn strip_terminator(line: &mut Vec<u8>) -> bool {
     if line.ends_with(b"\r\n") {
         line.truncate(line.len() - 2);
     } else if line.ends_with(b"\n") {
         line.truncate(line.len() - 1);
     } else {
-        return Err(ParseError::NotEnoughData.into());
+        return false;
     }
 
-    Ok(line)
+    true
 }
 
-pub(crate) fn read_last_line<R: Read>(bread: &mut BufReader<R>) -> Result<Vec<u8>> {
-    let mut line = Vec::new();
-    bread.read_until(b'\n', &mut line)?;
+/// Make the text of a font starting with `header`, where every required
+/// FIGcharacter is its own character on every row.
+#[cfg(test)]
+pub(crate) fn test_font(header: &str) -> String {
+    let height: usize = header.split_whitespace().nth(1).unwrap().parse().unwrap();
 
-    if line.ends_with(b"\r\n") {
-        line.truncate(line.len() - 2);
-    } else if line.ends_with(b"\n") {
-        line.truncate(line.len() - 1);
+    let mut font = format!("{}\n", header);
+    for c in (' '..='~').chain("\u{c4}\u{d6}\u{dc}\u{e4}\u{f6}\u{fc}\u{df}".chars()) {
+        let c = if c == ' ' { '$' } else { c };
+        for _ in 1..height {
+            font.push_str(&format!("{}@\n", c));
+        }
+        font.push_str(&format!("{}@@\n", c));
     }
 
-    Ok(line)
+    font
 }
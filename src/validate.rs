@@ -0,0 +1,162 @@
+use std::{
+    fmt::{Display, Formatter},
+    io::Read,
+    path::Path,
+};
+
+use crate::{error::ParseError, load_from, parse, result::Result, ParseOptions};
+
+/// The problems of a font, like figlet's chkfont reports them: everything
+/// a non-strict parse recovers from, plus what breaks the spec without
+/// stopping parsing, like lines longer than the max length or duplicate
+/// codetags. Problems preventing parsing at all are errors instead.
+///
+/// ```
+/// use figfont::Report;
+///
+/// let report = Report::load_from("fonts/plain/standard.flf").unwrap();
+/// assert!(report.is_valid(), "{}", report);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Report {
+    problems: Vec<ParseError>,
+}
+
+impl Report {
+    /// Validate a FIGfont or a TOIlet font from a path. It can be zipped,
+    /// gzipped or zstd compressed if you have the matching feature enabled.
+    pub fn load_from<P: AsRef<Path>>(path: P) -> Result<Report> {
+        Report::load_from_with(path, &ParseOptions::default())
+    }
+
+    /// Validate a FIGfont or a TOIlet font from a path with custom options.
+    /// `strict` is ignored.
+    pub fn load_from_with<P: AsRef<Path>>(path: P, options: &ParseOptions) -> Result<Report> {
+        let options = lenient(options);
+        load_from(path, &|f| parse(f, &options, true)).map(Report::new)
+    }
+
+    /// Validate a FIGfont from a impl Read.
+    pub fn read_from<R: Read>(reader: R) -> Result<Report> {
+        Report::read_from_with(reader, &ParseOptions::default())
+    }
+
+    /// Validate a FIGfont from a impl Read with custom options. `strict` is
+    /// ignored.
+    pub fn read_from_with<R: Read>(reader: R, options: &ParseOptions) -> Result<Report> {
+        parse(reader, &lenient(options), true).map(Report::new)
+    }
+
+    fn new<T>((_, mut problems): (T, Vec<ParseError>)) -> Self {
+        problems.sort_by_key(|problem| problem.offset());
+        Report { problems }
+    }
+
+    /// Get the problems, in the order they appear in the font.
+    pub fn problems(&self) -> &[ParseError] {
+        &self.problems
+    }
+
+    /// Check if the font has no problems.
+    pub fn is_valid(&self) -> bool {
+        self.problems.is_empty()
+    }
+}
+
+impl Display for Report {
+    fn fmt(&self, fmt: &mut Formatter<'_>) -> std::result::Result<(), std::fmt::Error> {
+        for problem in self.problems.iter() {
+            writeln!(fmt, "{}", problem)?;
+        }
+
+        Ok(())
+    }
+}
+
+fn lenient(options: &ParseOptions) -> ParseOptions {
+    ParseOptions {
+        strict: false,
+        lazy: false,
+        ..options.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Report;
+    use crate::{error::ParseErrorKind, utils::test_font, Codetag};
+
+    #[test]
+    fn problems() {
+        let mut font = test_font("flf2a$ 2 3 3 0 0 0 1");
+        let missing = font.split("\u{f6}@\n").next().unwrap().to_string();
+        font.push_str("0x263A\n\u{263a}@\n\u{263a}@@\n0x263A\nx@\nx@@\n");
+        let font = font.replacen("A@@", "AAA@@", 1).replacen("B@@", "B#", 1);
+
+        let report = Report::read_from(font.as_bytes()).unwrap();
+        let problems: Vec<_> = report
+            .problems()
+            .iter()
+            .map(|problem| (problem.kind(), problem.codetag()))
+            .collect();
+        assert_eq!(
+            problems,
+            [
+                (
+                    ParseErrorKind::InvalidBaseline {
+                        baseline: 3,
+                        height: 2
+                    },
+                    None
+                ),
+                (
+                    ParseErrorKind::LayoutMismatch {
+                        old_layout: 0,
+                        full_layout: 1
+                    },
+                    None
+                ),
+                (
+                    ParseErrorKind::LineTooLong {
+                        length: 5,
+                        max_length: 3
+                    },
+                    Some(Codetag::Char('A'))
+                ),
+                (
+                    ParseErrorKind::InconsistentWidth {
+                        expected: 1,
+                        found: 3
+                    },
+                    Some(Codetag::Char('A'))
+                ),
+                (
+                    ParseErrorKind::InconsistentEndmark {
+                        expected: '@',
+                        found: '#'
+                    },
+                    Some(Codetag::Char('B'))
+                ),
+                (
+                    ParseErrorKind::DuplicateCodetag,
+                    Some(Codetag::Char('\u{263a}'))
+                ),
+            ]
+        );
+        assert!(!report.is_valid());
+        assert_eq!(report.to_string().lines().count(), 6);
+
+        let report = Report::read_from(missing.as_bytes()).unwrap();
+        let missing: Vec<_> = report
+            .problems()
+            .iter()
+            .filter(|problem| problem.kind() == ParseErrorKind::MissingCharacter)
+            .filter_map(|problem| problem.codetag())
+            .collect();
+        assert_eq!(missing, [0xf6, 0xfc, 0xdf].map(Codetag::from));
+
+        assert!(Report::load_from("fonts/plain/standard.flf")
+            .unwrap()
+            .is_valid());
+    }
+}